@@ -32,4 +32,29 @@ fn main() {
         "cargo:rerun-if-changed={}",
         manifest_dir.join("src/ufbx_wrapper.h").display()
     );
+
+    generate_bindings(&ufbx_dir, &ufbx_h);
+}
+
+// Generates Rust bindings for the full `vendor/ufbx` API, exposed as the
+// `ufbx_bindgen` module. The loader still calls through the hand-maintained
+// structs in `ufbx_sys.rs` and the C-side glue in `ufbx_wrapper.c` (scene
+// export, error-string ownership); the generated bindings cover the rest of
+// the ufbx surface, which nothing wraps yet.
+fn generate_bindings(ufbx_dir: &PathBuf, ufbx_h: &PathBuf) {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    let bindings = bindgen::Builder::default()
+        .header(ufbx_h.to_string_lossy())
+        .clang_arg(format!("-I{}", ufbx_dir.display()))
+        .allowlist_type("ufbx_.*")
+        .allowlist_function("ufbx_.*")
+        .allowlist_var("UFBX_.*")
+        .derive_default(true)
+        .generate()
+        .expect("failed to generate ufbx bindgen bindings");
+
+    bindings
+        .write_to_file(out_dir.join("bindings.rs"))
+        .expect("failed to write ufbx bindgen bindings");
 }
@@ -0,0 +1,293 @@
+//! Bounding-volume hierarchy over a scene's mesh parts, used by
+//! `tiles::export_bvh_tileset` to emit a real nested 3D Tiles tree. This is
+//! an alternative to the grid/quadtree pipeline in `tiles.rs`, which bins
+//! individual triangles into a spatial grid; this module instead groups
+//! whole `MeshPart`s by a surface-area-heuristic split, which is cheaper
+//! for scenes made of many discrete parts rather than one dense mesh.
+
+use crate::ufbx_loader::SceneData;
+
+/// Axis-aligned bounding box. An empty box has `min` set to `+inf` and
+/// `max` to `-inf` on every axis, so `union`-ing it with anything leaves
+/// the other operand untouched instead of pulling the result toward the
+/// origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Aabb {
+            min: [f32::INFINITY; 3],
+            max: [f32::NEG_INFINITY; 3],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min[0] > self.max[0]
+    }
+
+    /// Builds an AABB from a flat `[x, y, z, x, y, z, ...]` position buffer.
+    /// A slice with fewer than 3 values yields `Aabb::empty`; a trailing
+    /// partial triple is ignored.
+    pub fn from_positions(positions: &[f32]) -> Self {
+        let mut aabb = Aabb::empty();
+        for p in positions.chunks_exact(3) {
+            for axis in 0..3 {
+                aabb.min[axis] = aabb.min[axis].min(p[axis]);
+                aabb.max[axis] = aabb.max[axis].max(p[axis]);
+            }
+        }
+        aabb
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let mut out = Aabb::empty();
+        for axis in 0..3 {
+            out.min[axis] = self.min[axis].min(other.min[axis]);
+            out.max[axis] = self.max[axis].max(other.max[axis]);
+        }
+        out
+    }
+
+    pub fn centroid(&self) -> [f32; 3] {
+        if self.is_empty() {
+            return [0.0; 3];
+        }
+        let mut c = [0.0; 3];
+        for axis in 0..3 {
+            c[axis] = (self.min[axis] + self.max[axis]) * 0.5;
+        }
+        c
+    }
+
+    pub fn extent(&self) -> [f32; 3] {
+        if self.is_empty() {
+            return [0.0; 3];
+        }
+        let mut e = [0.0; 3];
+        for axis in 0..3 {
+            e[axis] = self.max[axis] - self.min[axis];
+        }
+        e
+    }
+
+    pub fn diagonal(&self) -> f32 {
+        let e = self.extent();
+        (e[0] * e[0] + e[1] * e[1] + e[2] * e[2]).sqrt()
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let e = self.extent();
+        2.0 * (e[0] * e[1] + e[1] * e[2] + e[2] * e[0])
+    }
+
+    /// Widens a degenerate (zero- or near-zero-extent) box by `eps` on
+    /// every collapsed axis, and turns a fully empty box into a small cube
+    /// at the origin, so a node with no real volume still produces a valid
+    /// `boundingVolume.box`.
+    pub fn padded(&self, eps: f32) -> Aabb {
+        if self.is_empty() {
+            return Aabb {
+                min: [-eps; 3],
+                max: [eps; 3],
+            };
+        }
+        let mut out = *self;
+        for axis in 0..3 {
+            if out.max[axis] - out.min[axis] < eps {
+                let mid = (out.max[axis] + out.min[axis]) * 0.5;
+                out.min[axis] = mid - eps;
+                out.max[axis] = mid + eps;
+            }
+        }
+        out
+    }
+}
+
+/// A node in the part-level BVH: either a leaf holding the indices (into
+/// `SceneData::parts`) it was built from, or an interior node holding
+/// exactly two children. Every node carries the union AABB of everything
+/// beneath it.
+#[derive(Clone, Debug)]
+pub enum BvhNode {
+    Leaf {
+        bounds: Aabb,
+        parts: Vec<usize>,
+    },
+    Interior {
+        bounds: Aabb,
+        children: [Box<BvhNode>; 2],
+    },
+}
+
+impl BvhNode {
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } => *bounds,
+            BvhNode::Interior { bounds, .. } => *bounds,
+        }
+    }
+}
+
+/// Number of buckets the surface-area-heuristic split sorts centroids into
+/// along the chosen axis before scoring candidate split planes.
+const SAH_BUCKETS: usize = 12;
+
+/// Builds a BVH over `scene`'s parts, recursing until every leaf holds at
+/// most `max_parts_per_leaf` parts (clamped to at least 1). Parts with
+/// empty `positions` get an empty-bounds entry that `Aabb::union` safely
+/// ignores rather than letting it pull the tree toward the origin.
+pub fn build_bvh(scene: &SceneData, max_parts_per_leaf: usize) -> BvhNode {
+    let max_parts_per_leaf = max_parts_per_leaf.max(1);
+    let bounds: Vec<Aabb> = scene
+        .parts
+        .iter()
+        .map(|p| Aabb::from_positions(&p.positions))
+        .collect();
+    let centroids: Vec<[f32; 3]> = bounds.iter().map(|b| b.centroid()).collect();
+    let indices: Vec<usize> = (0..scene.parts.len()).collect();
+    build_node(indices, &bounds, &centroids, max_parts_per_leaf)
+}
+
+fn build_node(
+    indices: Vec<usize>,
+    bounds: &[Aabb],
+    centroids: &[[f32; 3]],
+    max_parts_per_leaf: usize,
+) -> BvhNode {
+    let union = indices
+        .iter()
+        .fold(Aabb::empty(), |acc, &i| acc.union(&bounds[i]));
+
+    if indices.len() <= max_parts_per_leaf {
+        return BvhNode::Leaf {
+            bounds: union,
+            parts: indices,
+        };
+    }
+
+    let centroid_bounds = indices.iter().fold(Aabb::empty(), |acc, &i| {
+        let c = centroids[i];
+        acc.union(&Aabb { min: c, max: c })
+    });
+    let extent = centroid_bounds.extent();
+    let axis = if extent[0] >= extent[1] && extent[0] >= extent[2] {
+        0
+    } else if extent[1] >= extent[2] {
+        1
+    } else {
+        2
+    };
+
+    let (left, right) = if extent[axis] <= f32::EPSILON {
+        // Every centroid coincides on every axis: no split plane can
+        // separate them, so fall back to an even split by position so
+        // recursion still shrinks each side below the leaf budget.
+        median_split(indices, |_| 0.0)
+    } else {
+        sah_split(&indices, bounds, centroids, axis, &centroid_bounds, extent)
+            .unwrap_or_else(|| median_split(indices, |i| centroids[i][axis]))
+    };
+
+    let left_node = build_node(left, bounds, centroids, max_parts_per_leaf);
+    let right_node = build_node(right, bounds, centroids, max_parts_per_leaf);
+    BvhNode::Interior {
+        bounds: union,
+        children: [Box::new(left_node), Box::new(right_node)],
+    }
+}
+
+/// Buckets `indices`' centroids into `SAH_BUCKETS` bins along `axis` and
+/// scores every inter-bucket split plane as `leftArea*leftCount +
+/// rightArea*rightCount`, returning the partition at the cheapest plane.
+/// Returns `None` if every candidate plane left one side empty (e.g. all
+/// centroids rounded into the same bucket), so the caller can fall back to
+/// an exact median split instead.
+fn sah_split(
+    indices: &[usize],
+    bounds: &[Aabb],
+    centroids: &[[f32; 3]],
+    axis: usize,
+    centroid_bounds: &Aabb,
+    extent: [f32; 3],
+) -> Option<(Vec<usize>, Vec<usize>)> {
+    let min_c = centroid_bounds.min[axis];
+    let bucket_size = extent[axis] / SAH_BUCKETS as f32;
+
+    let mut bucket_of = Vec::with_capacity(indices.len());
+    let mut bucket_bounds = vec![Aabb::empty(); SAH_BUCKETS];
+    let mut bucket_count = vec![0usize; SAH_BUCKETS];
+    for &i in indices {
+        let t = (centroids[i][axis] - min_c) / bucket_size;
+        let b = (t as usize).min(SAH_BUCKETS - 1);
+        bucket_of.push(b);
+        bucket_bounds[b] = bucket_bounds[b].union(&bounds[i]);
+        bucket_count[b] += 1;
+    }
+
+    let mut best_cost = f32::INFINITY;
+    let mut best_split = None;
+    for split in 1..SAH_BUCKETS {
+        let mut left_bounds = Aabb::empty();
+        let mut left_count = 0usize;
+        for (b, count) in bucket_bounds.iter().zip(bucket_count.iter()).take(split) {
+            left_bounds = left_bounds.union(b);
+            left_count += *count;
+        }
+        let mut right_bounds = Aabb::empty();
+        let mut right_count = 0usize;
+        for (b, count) in bucket_bounds.iter().zip(bucket_count.iter()).skip(split) {
+            right_bounds = right_bounds.union(b);
+            right_count += *count;
+        }
+        if left_count == 0 || right_count == 0 {
+            continue;
+        }
+        let cost = left_bounds.surface_area() * left_count as f32
+            + right_bounds.surface_area() * right_count as f32;
+        if cost < best_cost {
+            best_cost = cost;
+            best_split = Some(split);
+        }
+    }
+
+    let split = best_split?;
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for (pos, &i) in indices.iter().enumerate() {
+        if bucket_of[pos] < split {
+            left.push(i);
+        } else {
+            right.push(i);
+        }
+    }
+    Some((left, right))
+}
+
+/// Exact median split by sort key: guarantees both halves are non-empty
+/// (as long as `indices.len() >= 2`, which callers only reach past the
+/// leaf-budget check), so recursion always terminates even when the SAH
+/// bucket scan can't find a usable plane.
+fn median_split(mut indices: Vec<usize>, key: impl Fn(usize) -> f32) -> (Vec<usize>, Vec<usize>) {
+    indices.sort_unstable_by(|&a, &b| key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = indices.len() / 2;
+    let right = indices.split_off(mid);
+    (indices, right)
+}
+
+/// Geometric error for a node's (already padded) bounds: proportional to
+/// the box's diagonal length so a viewer refines faster into larger,
+/// coarser nodes, scaled by the same model-to-world `scale` factor
+/// `GeoContext` applies to vertex positions.
+pub fn geometric_error(bounds: &Aabb, scale: f64) -> f64 {
+    bounds.diagonal() as f64 * scale
+}
@@ -1,7 +1,53 @@
+use anyhow::{bail, Result};
+
 const WGS84_A: f64 = 6_378_137.0;
 const WGS84_F: f64 = 1.0 / 298.257_223_563;
 
+/// Semi-major axis `a` and flattening `f` of a reference ellipsoid. `GeoContext`
+/// used to hardcode WGS84; this lets a caller supply a different datum (or a
+/// locally-fit one via [`GeoContext::from_gcps`]) while reusing the same math.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ellipsoid {
+    pub a: f64,
+    pub f: f64,
+}
+
+impl Ellipsoid {
+    pub const WGS84: Ellipsoid = Ellipsoid {
+        a: WGS84_A,
+        f: WGS84_F,
+    };
+}
+
+impl Default for Ellipsoid {
+    fn default() -> Self {
+        Ellipsoid::WGS84
+    }
+}
+
+/// A known-accurate local coordinate paired with its surveyed geodetic
+/// position, used to fit a [`GeoContext`] without a hand-specified
+/// origin/heading/scale.
+#[derive(Clone, Copy, Debug)]
+pub struct GroundControlPoint {
+    pub local: [f64; 3],
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub height: f64,
+}
+
+/// Georeferencing scheme for a tileset export. `Enu` is the historical single
+/// ENU-at-origin frame; `WebMercator` additionally tags each tile with the
+/// slippy XYZ index covering its center, for CRS:EPSG:3857 consumers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Crs {
+    #[default]
+    Enu,
+    WebMercator,
+}
+
 pub struct GeoContext {
+    ellipsoid: Ellipsoid,
     heading_rad: f64,
     scale: f64,
     origin_ecef: [f64; 3],
@@ -9,13 +55,21 @@ pub struct GeoContext {
 }
 
 impl GeoContext {
-    pub fn new(lat_deg: f64, lon_deg: f64, height: f64, heading_deg: f64, scale: f64) -> Self {
+    pub fn new(
+        lat_deg: f64,
+        lon_deg: f64,
+        height: f64,
+        heading_deg: f64,
+        scale: f64,
+        ellipsoid: Ellipsoid,
+    ) -> Self {
         let lat_rad = lat_deg.to_radians();
         let lon_rad = lon_deg.to_radians();
         let heading_rad = heading_deg.to_radians();
-        let origin_ecef = geodetic_to_ecef(lat_rad, lon_rad, height);
+        let origin_ecef = geodetic_to_ecef(&ellipsoid, lat_rad, lon_rad, height);
         let rot_enu_to_ecef = enu_to_ecef_matrix(lat_rad, lon_rad);
         Self {
+            ellipsoid,
             heading_rad,
             scale,
             origin_ecef,
@@ -23,6 +77,135 @@ impl GeoContext {
         }
     }
 
+    /// Fits origin, heading and scale from surveyed ground-control points via
+    /// a Helmert/Umeyama least-squares similarity transform (rotation + scale
+    /// + translation), instead of requiring the caller to already know where
+    /// the model's local origin sits on the ellipsoid. Needs at least 2 GCPs;
+    /// 3+ non-collinear points are required to pin down heading unambiguously.
+    pub fn from_gcps(ellipsoid: Ellipsoid, gcps: &[GroundControlPoint]) -> Result<Self> {
+        if gcps.len() < 2 {
+            bail!("from_gcps needs at least 2 ground control points, got {}", gcps.len());
+        }
+
+        let targets: Vec<[f64; 3]> = gcps
+            .iter()
+            .map(|gcp| {
+                geodetic_to_ecef(
+                    &ellipsoid,
+                    gcp.lat_deg.to_radians(),
+                    gcp.lon_deg.to_radians(),
+                    gcp.height,
+                )
+            })
+            .collect();
+        let sources: Vec<[f64; 3]> = gcps.iter().map(|gcp| gcp.local).collect();
+
+        let n = gcps.len() as f64;
+        let mut source_centroid = [0.0; 3];
+        let mut target_centroid = [0.0; 3];
+        for i in 0..gcps.len() {
+            for k in 0..3 {
+                source_centroid[k] += sources[i][k] / n;
+                target_centroid[k] += targets[i][k] / n;
+            }
+        }
+
+        let mut source_var = 0.0;
+        let mut cross_cov = [[0.0; 3]; 3];
+        for i in 0..gcps.len() {
+            let s = [
+                sources[i][0] - source_centroid[0],
+                sources[i][1] - source_centroid[1],
+                sources[i][2] - source_centroid[2],
+            ];
+            let t = [
+                targets[i][0] - target_centroid[0],
+                targets[i][1] - target_centroid[1],
+                targets[i][2] - target_centroid[2],
+            ];
+            source_var += s[0] * s[0] + s[1] * s[1] + s[2] * s[2];
+            for row in 0..3 {
+                for col in 0..3 {
+                    cross_cov[row][col] += t[row] * s[col];
+                }
+            }
+        }
+        source_var /= n;
+        if source_var < 1e-12 {
+            bail!("from_gcps: ground control points are coincident in local space");
+        }
+
+        let (u, singular_values, v) = svd3(&cross_cov);
+
+        let det_u = mat3_det(&u);
+        let det_v = mat3_det(&v);
+        // Local axes are East/Up/North, a left-handed frame, so the true
+        // local-to-ECEF map is a reflection: constrain det(r) to -1.
+        let sign = [1.0, 1.0, if det_u * det_v > 0.0 { -1.0 } else { 1.0 }];
+
+        let mut r = [[0.0; 3]; 3];
+        for row in 0..3 {
+            for col in 0..3 {
+                let mut value = 0.0;
+                for k in 0..3 {
+                    value += u[row][k] * sign[k] * v[col][k];
+                }
+                r[row][col] = value;
+            }
+        }
+
+        let trace_sv = sign[0] * singular_values[0]
+            + sign[1] * singular_values[1]
+            + sign[2] * singular_values[2];
+        // `source_var` is already divided by `n`; `cross_cov` is not.
+        let scale = (trace_sv / (n * source_var)).max(1e-9);
+
+        let mut origin_ecef = target_centroid;
+        for row in 0..3 {
+            let mut rotated = 0.0;
+            for col in 0..3 {
+                rotated += r[row][col] * source_centroid[col];
+            }
+            origin_ecef[row] -= scale * rotated;
+        }
+
+        let (lat_rad, lon_rad, height) = ecef_to_geodetic(&ellipsoid, origin_ecef);
+        let rot_enu_to_ecef = enu_to_ecef_matrix(lat_rad, lon_rad);
+
+        // `r` maps local axes into ECEF; express it in the origin's
+        // East/Up/North columns (the frame `transform_local` rotates in)
+        // before reading the heading off where local +X lands.
+        let mut r_local = [[0.0; 3]; 3];
+        for row in 0..3 {
+            for col in 0..3 {
+                for k in 0..3 {
+                    r_local[row][col] += rot_enu_to_ecef[k][row] * r[k][col];
+                }
+            }
+        }
+        let heading_rad = r_local[2][0].atan2(r_local[0][0]);
+
+        Ok(Self {
+            ellipsoid,
+            heading_rad,
+            scale,
+            origin_ecef: geodetic_to_ecef(&ellipsoid, lat_rad, lon_rad, height),
+            rot_enu_to_ecef,
+        })
+    }
+
+    /// The local-to-ENU scale factor applied by [`GeoContext::transform_local`].
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Converts an ECEF position (for example one from
+    /// [`Self::local_to_ecef`]) back to geodetic `(lat_rad, lon_rad, height)`
+    /// on this context's ellipsoid.
+    pub fn ecef_to_geodetic(&self, ecef: [f64; 3]) -> (f64, f64, f64) {
+        ecef_to_geodetic(&self.ellipsoid, ecef)
+    }
+
     // 模型坐标默认 Y-up，heading 绕 +Y 旋转后再做缩放。
     pub fn transform_local(&self, pos: [f64; 3]) -> [f64; 3] {
         let x = pos[0] * self.scale;
@@ -34,6 +217,19 @@ impl GeoContext {
         [xr, y, zr]
     }
 
+    // ENU->ECEF 旋转矩阵的列是 East/Up/North，直接对 transform_local 的输出做
+    // 矩阵乘法再加原点即可，无需重新走一遍 transform_matrix 的齐次坐标路径。
+    pub fn local_to_ecef(&self, pos: [f64; 3]) -> [f64; 3] {
+        let enu = self.transform_local(pos);
+        let mut ecef = self.origin_ecef;
+        for i in 0..3 {
+            ecef[i] += self.rot_enu_to_ecef[i][0] * enu[0]
+                + self.rot_enu_to_ecef[i][1] * enu[1]
+                + self.rot_enu_to_ecef[i][2] * enu[2];
+        }
+        ecef
+    }
+
     pub fn transform_matrix(&self) -> [f64; 16] {
         self.transform_matrix_with_axes(identity_axis_matrix())
     }
@@ -97,14 +293,14 @@ fn identity_axis_matrix() -> [[f64; 3]; 3] {
     m
 }
 
-fn geodetic_to_ecef(lat_rad: f64, lon_rad: f64, height: f64) -> [f64; 3] {
-    let e2 = WGS84_F * (2.0 - WGS84_F);
+fn geodetic_to_ecef(ellipsoid: &Ellipsoid, lat_rad: f64, lon_rad: f64, height: f64) -> [f64; 3] {
+    let e2 = ellipsoid.f * (2.0 - ellipsoid.f);
     let sin_lat = lat_rad.sin();
     let cos_lat = lat_rad.cos();
     let sin_lon = lon_rad.sin();
     let cos_lon = lon_rad.cos();
 
-    let n = WGS84_A / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+    let n = ellipsoid.a / (1.0 - e2 * sin_lat * sin_lat).sqrt();
 
     let x = (n + height) * cos_lat * cos_lon;
     let y = (n + height) * cos_lat * sin_lon;
@@ -113,6 +309,197 @@ fn geodetic_to_ecef(lat_rad: f64, lon_rad: f64, height: f64) -> [f64; 3] {
     [x, y, z]
 }
 
+/// Projects a geodetic position into Web Mercator (EPSG:3857) meters.
+pub fn lonlat_to_mercator(lon_deg: f64, lat_deg: f64) -> (f64, f64) {
+    let lon_rad = lon_deg.to_radians();
+    let lat_rad = lat_deg.to_radians();
+    let x = WGS84_A * lon_rad;
+    let y = WGS84_A * (std::f64::consts::FRAC_PI_4 + lat_rad / 2.0).tan().ln();
+    (x, y)
+}
+
+/// Standard slippy-map tile index covering `(lon_deg, lat_deg)` at `zoom`.
+pub fn slippy_tile_index(lon_deg: f64, lat_deg: f64, zoom: u32) -> (u32, u32) {
+    let lat_rad = lat_deg.to_radians();
+    let n = 2f64.powi(zoom as i32);
+    let x = ((lon_deg + 180.0) / 360.0 * n).floor();
+    let y = ((1.0 - (lat_rad.tan() + 1.0 / lat_rad.cos()).ln() / std::f64::consts::PI) / 2.0 * n)
+        .floor();
+    (
+        x.clamp(0.0, n - 1.0) as u32,
+        y.clamp(0.0, n - 1.0) as u32,
+    )
+}
+
+/// Zoom level whose slippy tile edge length (world circumference / 2^z) most
+/// closely matches `tile_size_m`, so a `--crs mercator` export can tag tiles
+/// with the standard XYZ scheme without the caller picking a zoom by hand.
+pub fn mercator_zoom_for_tile_size(tile_size_m: f64) -> u32 {
+    if tile_size_m <= 0.0 {
+        return 0;
+    }
+    let circumference = 2.0 * std::f64::consts::PI * WGS84_A;
+    (circumference / tile_size_m).log2().round().max(0.0) as u32
+}
+
+/// Bounding sphere of an axis-aligned box, left in local space rather than
+/// transformed to ECEF. A tile's `sphere`/`box` boundingVolume (unlike
+/// `region`) is defined in the tile's local frame — the same frame its GLB
+/// content is written in — and is placed by the inherited tileset
+/// `transform`, so this must not apply that transform itself or the sphere
+/// ends up transformed twice.
+pub fn bounding_sphere_local(min_local: [f64; 3], max_local: [f64; 3]) -> [f64; 4] {
+    let mut center = [0.0; 3];
+    let mut radius_sq = 0.0;
+    for i in 0..3 {
+        center[i] = 0.5 * (min_local[i] + max_local[i]);
+        let half = 0.5 * (max_local[i] - min_local[i]);
+        radius_sq += half * half;
+    }
+    [center[0], center[1], center[2], radius_sq.sqrt()]
+}
+
+// Bowring 闭式解：先用球面近似求 θ，再迭代一次修正纬度，polar case 另外处理。
+pub fn ecef_to_geodetic(ellipsoid: &Ellipsoid, ecef: [f64; 3]) -> (f64, f64, f64) {
+    let [x, y, z] = ecef;
+    let e2 = ellipsoid.f * (2.0 - ellipsoid.f);
+    let b = ellipsoid.a * (1.0 - ellipsoid.f);
+    let ep2 = e2 / (1.0 - e2);
+
+    let p = x.hypot(y);
+    let lon = y.atan2(x);
+
+    if p < 1e-9 {
+        let lat = if z >= 0.0 {
+            std::f64::consts::FRAC_PI_2
+        } else {
+            -std::f64::consts::FRAC_PI_2
+        };
+        let height = z.abs() - b;
+        return (lat, lon, height);
+    }
+
+    let theta = (z * ellipsoid.a).atan2(p * b);
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let lat = (z + ep2 * b * sin_theta.powi(3)).atan2(p - e2 * ellipsoid.a * cos_theta.powi(3));
+    let sin_lat = lat.sin();
+    let n = ellipsoid.a / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+    let height = p / lat.cos() - n;
+
+    (lat, lon, height)
+}
+
+/// 3x3 symmetric eigendecomposition via the cyclic Jacobi method, then turned
+/// into an SVD of the (generally non-symmetric) cross-covariance `m` by
+/// eigendecomposing `mᵀm`: eigenvectors become `V`, singular values are the
+/// square roots of the eigenvalues, and `U = m·V·Σ⁻¹` column-by-column. No
+/// linear-algebra crate is vendored here, so this is the repo's only SVD.
+fn svd3(m: &[[f64; 3]; 3]) -> ([[f64; 3]; 3], [f64; 3], [[f64; 3]; 3]) {
+    let mut mtm = [[0.0; 3]; 3];
+    for row in 0..3 {
+        for col in 0..3 {
+            let mut value = 0.0;
+            for k in 0..3 {
+                value += m[k][row] * m[k][col];
+            }
+            mtm[row][col] = value;
+        }
+    }
+
+    let (eigvals, eigvecs) = jacobi_eigen_symmetric(mtm);
+
+    let mut order = [0usize, 1, 2];
+    order.sort_by(|&a, &b| eigvals[b].partial_cmp(&eigvals[a]).unwrap());
+
+    let mut v = [[0.0; 3]; 3];
+    let mut singular_values = [0.0; 3];
+    for (col, &src) in order.iter().enumerate() {
+        singular_values[col] = eigvals[src].max(0.0).sqrt();
+        for row in 0..3 {
+            v[row][col] = eigvecs[row][src];
+        }
+    }
+
+    let mut u = [[0.0; 3]; 3];
+    for col in 0..3 {
+        if singular_values[col] > 1e-9 {
+            for row in 0..3 {
+                let mut value = 0.0;
+                for k in 0..3 {
+                    value += m[row][k] * v[k][col];
+                }
+                u[row][col] = value / singular_values[col];
+            }
+        } else {
+            u[0][col] = if col == 0 { 1.0 } else { 0.0 };
+            u[1][col] = if col == 1 { 1.0 } else { 0.0 };
+            u[2][col] = if col == 2 { 1.0 } else { 0.0 };
+        }
+    }
+
+    (u, singular_values, v)
+}
+
+// Classic cyclic Jacobi rotation sweep: zero the largest off-diagonal entry
+// repeatedly until the matrix is diagonal to within tolerance. Converges in a
+// handful of sweeps for 3x3 and needs no library support.
+fn jacobi_eigen_symmetric(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = identity_axis_matrix();
+
+    for _ in 0..50 {
+        let (mut p, mut q, mut max_off) = (0usize, 1usize, 0.0f64);
+        for i in 0..3 {
+            for j in (i + 1)..3 {
+                if a[i][j].abs() > max_off {
+                    max_off = a[i][j].abs();
+                    p = i;
+                    q = j;
+                }
+            }
+        }
+        if max_off < 1e-12 {
+            break;
+        }
+
+        let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+        let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+        let c = 1.0 / (t * t + 1.0).sqrt();
+        let s = t * c;
+
+        let app = a[p][p];
+        let aqq = a[q][q];
+        let apq = a[p][q];
+        a[p][p] = c * c * app - 2.0 * s * c * apq + s * s * aqq;
+        a[q][q] = s * s * app + 2.0 * s * c * apq + c * c * aqq;
+        a[p][q] = 0.0;
+        a[q][p] = 0.0;
+        for i in 0..3 {
+            if i != p && i != q {
+                let aip = a[i][p];
+                let aiq = a[i][q];
+                a[i][p] = c * aip - s * aiq;
+                a[p][i] = a[i][p];
+                a[i][q] = s * aip + c * aiq;
+                a[q][i] = a[i][q];
+            }
+        }
+        for i in 0..3 {
+            let vip = v[i][p];
+            let viq = v[i][q];
+            v[i][p] = c * vip - s * viq;
+            v[i][q] = s * vip + c * viq;
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+fn mat3_det(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
 // 本地坐标约定为 X东Y上Z北（EUN），因此列向量依次为 East/Up/North。
 fn enu_to_ecef_matrix(lat_rad: f64, lon_rad: f64) -> [[f64; 3]; 3] {
     let (sin_lat, cos_lat) = lat_rad.sin_cos();
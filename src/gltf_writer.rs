@@ -1,10 +1,12 @@
-use crate::image_utils::{encode_texture, ImageData};
-use crate::ufbx_loader::{SceneData, TextureSource};
+use crate::image_utils::{encode_texture, ImageCache, ImageData, TextureOptions};
+use crate::ufbx_loader::{AlphaMode, SceneData, TextureSource};
 use anyhow::{bail, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde_json::{json, Map, Value};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::fs::{self, File};
+use std::fs;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -15,6 +17,7 @@ const CHUNK_TYPE_JSON: u32 = 0x4E4F534A;
 const CHUNK_TYPE_BIN: u32 = 0x004E4942;
 
 const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
 
 pub struct TextureCache {
     pub dir: PathBuf,
@@ -37,6 +40,40 @@ pub enum TextureMode<'a> {
     External(&'a mut TextureCache),
 }
 
+/// Vertex attribute compression applied before a GLB is written.
+///
+/// This only covers `KHR_mesh_quantization`. The meshoptimizer-backed
+/// option originally requested alongside it — vertex-cache reordering plus
+/// the `EXT_meshopt_compression` vertex/index codec — was descoped: an
+/// earlier pass added a `Meshopt` variant that was a no-op alias of
+/// `Quantized`, and that was removed rather than shipped as a fake codec.
+/// Implementing the real meshopt codec is still an open request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeshCompression {
+    #[default]
+    None,
+    /// KHR_mesh_quantization: positions as normalized `u16` (decoded back to
+    /// world units via the mesh node's `matrix`), normals as normalized
+    /// `i8`, UVs as normalized `u16`. Plain byte-normalized VEC3 rather than
+    /// octahedral encoding, so the NORMAL accessor stays within what
+    /// standard glTF viewers already understand.
+    Quantized,
+}
+
+/// Whole-file compression applied to a written GLB (or, via
+/// `write_compressed_file`, `tiles.rs`'s tileset JSON). The gzip-encoded
+/// bytes are written under the file's normal path rather than a `.gz`
+/// sibling, so every `content.uri`/file reference elsewhere stays valid; a
+/// host serving the tileset is expected to send `Content-Encoding: gzip`
+/// for these paths, the same way a CDN or object store serves
+/// pre-compressed assets transparently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TileCompression {
+    #[default]
+    None,
+    Gzip,
+}
+
 struct TextureRef {
     texture_index: usize,
     has_alpha: bool,
@@ -60,57 +97,124 @@ impl ImageEntry {
     }
 }
 
-pub fn write_glb(scene: &SceneData, path: &Path) -> Result<()> {
-    let mut mode = TextureMode::Embed;
-    write_glb_with_textures(scene, path, &mut mode)
+pub fn write_glb_with_options(
+    scene: &SceneData,
+    path: &Path,
+    texture_mode: &mut TextureMode,
+    texture_options: &TextureOptions,
+) -> Result<()> {
+    write_glb_with_compression(
+        scene,
+        path,
+        texture_mode,
+        texture_options,
+        MeshCompression::None,
+        TileCompression::None,
+        6,
+    )
 }
 
-pub fn write_glb_with_textures(
+#[allow(clippy::too_many_arguments)]
+pub fn write_glb_with_compression(
     scene: &SceneData,
     path: &Path,
     texture_mode: &mut TextureMode,
+    texture_options: &TextureOptions,
+    mesh_compression: MeshCompression,
+    tile_compression: TileCompression,
+    compression_level: u32,
 ) -> Result<()> {
     let mut buffer = BufferBuilder::default();
     let mut buffer_views = Vec::new();
     let mut accessors = Vec::new();
     let mut primitives = Vec::new();
 
+    // A single node (and thus a single decode transform) covers every part
+    // in this GLB, so quantized positions need one shared bounding box
+    // rather than a per-part one.
+    let quantize = mesh_compression != MeshCompression::None;
+    let position_bounds = if quantize {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for part in &scene.parts {
+            if part.positions.is_empty() {
+                continue;
+            }
+            let (pmin, pmax) = min_max_vec3(&part.positions);
+            for i in 0..3 {
+                min[i] = min[i].min(pmin[i]);
+                max[i] = max[i].max(pmax[i]);
+            }
+        }
+        Some((min, max))
+    } else {
+        None
+    };
+
     for part in &scene.parts {
         if part.positions.is_empty() {
             continue;
         }
         let positions = &part.positions;
         let vertex_count = positions.len() / 3;
-        let normals = ensure_normals(positions, &part.normals);
+        let normals = match &part.indices {
+            Some(indices) => ensure_normals_indexed(positions, &part.normals, indices),
+            None => ensure_normals(positions, &part.normals),
+        };
         let uvs = ensure_uvs(vertex_count, &part.uvs);
         let colors = ensure_colors(vertex_count, &part.colors);
-        let tangents = compute_tangents(positions, &uvs, &normals);
+        let tangents = match &part.indices {
+            Some(indices) => compute_tangents_indexed(positions, &uvs, &normals, indices),
+            None => compute_tangents(positions, &uvs, &normals),
+        };
 
-        let (pos_accessor, min, max) = push_accessor_vec3(
-            &mut buffer,
-            &mut buffer_views,
-            &mut accessors,
-            positions,
-            TARGET_ARRAY_BUFFER,
-        )?;
-        update_accessor_bounds(&mut accessors[pos_accessor], min, max);
+        let pos_accessor = if let Some((min, max)) = position_bounds {
+            let (accessor, accessor_min, accessor_max) = push_accessor_vec3_quantized(
+                &mut buffer,
+                &mut buffer_views,
+                &mut accessors,
+                positions,
+                min,
+                max,
+            )?;
+            update_accessor_bounds(&mut accessors[accessor], accessor_min, accessor_max);
+            accessor
+        } else {
+            let (accessor, min, max) = push_accessor_vec3(
+                &mut buffer,
+                &mut buffer_views,
+                &mut accessors,
+                positions,
+                TARGET_ARRAY_BUFFER,
+            )?;
+            update_accessor_bounds(&mut accessors[accessor], min, max);
+            accessor
+        };
 
-        let normal_accessor = push_accessor_vec3(
-            &mut buffer,
-            &mut buffer_views,
-            &mut accessors,
-            &normals,
-            TARGET_ARRAY_BUFFER,
-        )?
-        .0;
-        let uv_accessor = push_accessor_vec2(
-            &mut buffer,
-            &mut buffer_views,
-            &mut accessors,
-            &uvs,
-            TARGET_ARRAY_BUFFER,
-        )?
-        .0;
+        let normal_accessor = if quantize {
+            push_accessor_vec3_normal_quantized(&mut buffer, &mut buffer_views, &mut accessors, &normals)?
+        } else {
+            push_accessor_vec3(
+                &mut buffer,
+                &mut buffer_views,
+                &mut accessors,
+                &normals,
+                TARGET_ARRAY_BUFFER,
+            )?
+            .0
+        };
+        let uv_accessor = if quantize {
+            push_accessor_vec2_quantized(&mut buffer, &mut buffer_views, &mut accessors, &uvs)?
+        } else {
+            push_accessor_vec2(
+                &mut buffer,
+                &mut buffer_views,
+                &mut accessors,
+                &uvs,
+                TARGET_ARRAY_BUFFER,
+            )?
+            .0
+        };
         let color_accessor = push_accessor_vec4(
             &mut buffer,
             &mut buffer_views,
@@ -141,11 +245,17 @@ pub fn write_glb_with_textures(
             0
         };
 
-        primitives.push(json!({
+        let mut primitive = json!({
             "attributes": Value::Object(attributes),
             "material": material_index,
             "mode": 4
-        }));
+        });
+        if let Some(indices) = &part.indices {
+            let indices_accessor =
+                push_accessor_indices(&mut buffer, &mut buffer_views, &mut accessors, indices)?;
+            primitive["indices"] = json!(indices_accessor);
+        }
+        primitives.push(primitive);
     }
 
     if primitives.is_empty() {
@@ -156,7 +266,7 @@ pub fn write_glb_with_textures(
     let mut textures = Vec::new();
     let mut samplers = Vec::new();
     let mut image_map = HashMap::<u64, usize>::new();
-    let mut texture_map = HashMap::<usize, usize>::new();
+    let mut texture_map = HashMap::<(usize, Option<usize>), usize>::new();
 
     let sampler_index = samplers.len();
     samplers.push(json!({
@@ -166,34 +276,69 @@ pub fn write_glb_with_textures(
         "wrapT": 10497
     }));
 
+    let mut extensions_used = Vec::new();
+    let mut image_cache = ImageCache::new();
     let mut materials = Vec::new();
     for material in &scene.materials {
         let base_color_texture = texture_index(
             &material.base_color_texture,
+            &mut image_cache,
             &mut images,
             &mut textures,
             &mut image_map,
             &mut texture_map,
             sampler_index,
             texture_mode,
+            texture_options,
+            &mut extensions_used,
         )?;
         let normal_texture = texture_index(
             &material.normal_texture,
+            &mut image_cache,
             &mut images,
             &mut textures,
             &mut image_map,
             &mut texture_map,
             sampler_index,
             texture_mode,
+            texture_options,
+            &mut extensions_used,
         )?;
         let emissive_texture = texture_index(
             &material.emissive_texture,
+            &mut image_cache,
+            &mut images,
+            &mut textures,
+            &mut image_map,
+            &mut texture_map,
+            sampler_index,
+            texture_mode,
+            texture_options,
+            &mut extensions_used,
+        )?;
+        let metallic_roughness_texture = texture_index(
+            &material.metallic_roughness_texture,
+            &mut image_cache,
             &mut images,
             &mut textures,
             &mut image_map,
             &mut texture_map,
             sampler_index,
             texture_mode,
+            texture_options,
+            &mut extensions_used,
+        )?;
+        let occlusion_texture = texture_index(
+            &material.occlusion_texture,
+            &mut image_cache,
+            &mut images,
+            &mut textures,
+            &mut image_map,
+            &mut texture_map,
+            sampler_index,
+            texture_mode,
+            texture_options,
+            &mut extensions_used,
         )?;
 
         let mut pbr = json!({
@@ -205,6 +350,9 @@ pub fn write_glb_with_textures(
         if let Some(tex) = &base_color_texture {
             pbr["baseColorTexture"] = json!({ "index": tex.texture_index });
         }
+        if let Some(tex) = &metallic_roughness_texture {
+            pbr["metallicRoughnessTexture"] = json!({ "index": tex.texture_index });
+        }
 
         let has_texture = base_color_texture.is_some()
             || normal_texture.is_some()
@@ -222,8 +370,17 @@ pub fn write_glb_with_textures(
         if has_texture {
             material_value["doubleSided"] = json!(true);
         }
-        if base_color_has_alpha || material.base_color[3] < 0.999 {
-            material_value["alphaMode"] = json!("BLEND");
+        match material.alpha_mode {
+            AlphaMode::Blend => material_value["alphaMode"] = json!("BLEND"),
+            AlphaMode::Mask => {
+                material_value["alphaMode"] = json!("MASK");
+                material_value["alphaCutoff"] = json!(material.alpha_cutoff);
+            }
+            AlphaMode::Opaque => {
+                if base_color_has_alpha || material.base_color[3] < 0.999 {
+                    material_value["alphaMode"] = json!("BLEND");
+                }
+            }
         }
 
         if let Some(tex) = normal_texture {
@@ -232,6 +389,13 @@ pub fn write_glb_with_textures(
         if let Some(tex) = emissive_texture {
             material_value["emissiveTexture"] = json!({ "index": tex.texture_index });
         }
+        if let Some(tex) = &occlusion_texture {
+            let mut occlusion = json!({ "index": tex.texture_index });
+            if (material.occlusion_strength - 1.0).abs() > f32::EPSILON {
+                occlusion["strength"] = json!(material.occlusion_strength);
+            }
+            material_value["occlusionTexture"] = occlusion;
+        }
         if material.emissive != [0.0, 0.0, 0.0] {
             material_value["emissiveFactor"] = json!(material.emissive);
         }
@@ -239,6 +403,119 @@ pub fn write_glb_with_textures(
             material_value["name"] = json!(name);
         }
 
+        let mut material_extensions = Map::new();
+
+        if let Some(clearcoat) = &material.clearcoat {
+            let clearcoat_texture = texture_index(
+                &clearcoat.texture,
+                &mut image_cache,
+                &mut images,
+                &mut textures,
+                &mut image_map,
+                &mut texture_map,
+                sampler_index,
+                texture_mode,
+                texture_options,
+                &mut extensions_used,
+            )?;
+            let clearcoat_roughness_texture = texture_index(
+                &clearcoat.roughness_texture,
+                &mut image_cache,
+                &mut images,
+                &mut textures,
+                &mut image_map,
+                &mut texture_map,
+                sampler_index,
+                texture_mode,
+                texture_options,
+                &mut extensions_used,
+            )?;
+            let mut ext = json!({
+                "clearcoatFactor": clearcoat.factor,
+                "clearcoatRoughnessFactor": clearcoat.roughness
+            });
+            if let Some(tex) = clearcoat_texture {
+                ext["clearcoatTexture"] = json!({ "index": tex.texture_index });
+            }
+            if let Some(tex) = clearcoat_roughness_texture {
+                ext["clearcoatRoughnessTexture"] = json!({ "index": tex.texture_index });
+            }
+            material_extensions.insert("KHR_materials_clearcoat".to_string(), ext);
+            extensions_used.push("KHR_materials_clearcoat".to_string());
+        }
+
+        if material.transmission_factor > 0.0 {
+            let transmission_texture = texture_index(
+                &material.transmission_texture,
+                &mut image_cache,
+                &mut images,
+                &mut textures,
+                &mut image_map,
+                &mut texture_map,
+                sampler_index,
+                texture_mode,
+                texture_options,
+                &mut extensions_used,
+            )?;
+            let mut ext = json!({ "transmissionFactor": material.transmission_factor });
+            if let Some(tex) = transmission_texture {
+                ext["transmissionTexture"] = json!({ "index": tex.texture_index });
+            }
+            material_extensions.insert("KHR_materials_transmission".to_string(), ext);
+            extensions_used.push("KHR_materials_transmission".to_string());
+        }
+
+        if let Some(sheen) = &material.sheen {
+            let sheen_color_texture = texture_index(
+                &sheen.color_texture,
+                &mut image_cache,
+                &mut images,
+                &mut textures,
+                &mut image_map,
+                &mut texture_map,
+                sampler_index,
+                texture_mode,
+                texture_options,
+                &mut extensions_used,
+            )?;
+            let sheen_roughness_texture = texture_index(
+                &sheen.roughness_texture,
+                &mut image_cache,
+                &mut images,
+                &mut textures,
+                &mut image_map,
+                &mut texture_map,
+                sampler_index,
+                texture_mode,
+                texture_options,
+                &mut extensions_used,
+            )?;
+            let mut ext = json!({
+                "sheenColorFactor": sheen.color,
+                "sheenRoughnessFactor": sheen.roughness
+            });
+            if let Some(tex) = sheen_color_texture {
+                ext["sheenColorTexture"] = json!({ "index": tex.texture_index });
+            }
+            if let Some(tex) = sheen_roughness_texture {
+                ext["sheenRoughnessTexture"] = json!({ "index": tex.texture_index });
+            }
+            material_extensions.insert("KHR_materials_sheen".to_string(), ext);
+            extensions_used.push("KHR_materials_sheen".to_string());
+        }
+
+        if (material.ior - 1.5).abs() > f32::EPSILON {
+            material_extensions.insert(
+                "KHR_materials_ior".to_string(),
+                json!({ "ior": material.ior }),
+            );
+            extensions_used.push("KHR_materials_ior".to_string());
+        }
+
+        if !material_extensions.is_empty() {
+            material_value["extensions"] = Value::Object(material_extensions);
+        }
+
         materials.push(material_value);
     }
 
@@ -267,7 +544,17 @@ pub fn write_glb_with_textures(
 
     let buffers = vec![json!({ "byteLength": buffer.data.len() })];
 
-    let gltf = json!({
+    let mut node = json!({ "mesh": 0 });
+    if let Some((min, max)) = position_bounds {
+        let scale = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+        node["matrix"] = json!([
+            scale[0], 0.0, 0.0, 0.0, 0.0, scale[1], 0.0, 0.0, 0.0, 0.0, scale[2], 0.0, min[0],
+            min[1], min[2], 1.0
+        ]);
+        extensions_used.push("KHR_mesh_quantization".to_string());
+    }
+
+    let mut gltf = json!({
         "asset": {
             "version": "2.0",
             "generator": "ufbx_rust"
@@ -280,70 +567,67 @@ pub fn write_glb_with_textures(
         "textures": textures,
         "materials": materials,
         "meshes": [ { "primitives": primitives } ],
-        "nodes": [ { "mesh": 0 } ],
+        "nodes": [ node ],
         "scenes": [ { "nodes": [0] } ],
         "scene": 0
     });
 
-    write_glb_container(path, gltf, buffer.data)
+    if !extensions_used.is_empty() {
+        extensions_used.sort();
+        extensions_used.dedup();
+        gltf["extensionsUsed"] = json!(extensions_used);
+    }
+    if quantize {
+        gltf["extensionsRequired"] = json!(["KHR_mesh_quantization"]);
+    }
+
+    write_glb_container(path, gltf, buffer.data, tile_compression, compression_level)
 }
 
 fn texture_index(
     texture: &Option<TextureSource>,
+    image_cache: &mut ImageCache,
     images: &mut Vec<ImageEntry>,
     textures: &mut Vec<Value>,
     image_map: &mut HashMap<u64, usize>,
-    texture_map: &mut HashMap<usize, usize>,
+    texture_map: &mut HashMap<(usize, Option<usize>), usize>,
     sampler_index: usize,
     texture_mode: &mut TextureMode,
+    texture_options: &TextureOptions,
+    extensions_used: &mut Vec<String>,
 ) -> Result<Option<TextureRef>> {
     let Some(texture) = texture else {
         return Ok(None);
     };
 
-    let Some(image) = encode_texture(texture)? else {
+    let Some(cache_index) = encode_texture(texture, texture_options, image_cache)? else {
         return Ok(None);
     };
-    let hash = hash_bytes(&image.bytes);
-
-    let image_index = if let Some(existing) = image_map.get(&hash) {
-        *existing
-    } else {
-        let entry = match texture_mode {
-            TextureMode::Embed => ImageEntry::Embedded(image),
-            TextureMode::External(cache) => {
-                let ext = if image.mime_type == "image/png" { "png" } else { "jpg" };
-                let filename = cache
-                    .map
-                    .entry(hash)
-                    .or_insert_with(|| format!("tex_{hash:016x}.{ext}"))
-                    .clone();
-                let path = cache.dir.join(&filename);
-                if !path.exists() {
-                    fs::write(&path, &image.bytes)
-                        .with_context(|| format!("write texture {}", path.display()))?;
-                }
-                let prefix = cache.uri_prefix.trim_end_matches('/');
-                let uri = if prefix.is_empty() {
-                    filename
-                } else {
-                    format!("{}/{}", prefix, filename)
-                };
-                ImageEntry::External {
-                    uri,
-                    mime_type: image.mime_type,
-                    has_alpha: image.has_alpha,
-                }
-            }
-        };
-        let idx = images.len();
-        images.push(entry);
-        image_map.insert(hash, idx);
-        idx
+    let image = &image_cache.entries[cache_index];
+
+    let primary_index = register_image(
+        &image.bytes,
+        &image.mime_type,
+        image.has_alpha,
+        texture_mode,
+        images,
+        image_map,
+    )?;
+    let fallback_index = match &image.fallback {
+        Some(fallback) => Some(register_image(
+            &fallback.bytes,
+            &fallback.mime_type,
+            image.has_alpha,
+            texture_mode,
+            images,
+            image_map,
+        )?),
+        None => None,
     };
 
-    let has_alpha = images[image_index].has_alpha();
-    if let Some(existing) = texture_map.get(&image_index) {
+    let has_alpha = images[primary_index].has_alpha();
+    let texture_key = (primary_index, fallback_index);
+    if let Some(existing) = texture_map.get(&texture_key) {
         return Ok(Some(TextureRef {
             texture_index: *existing,
             has_alpha,
@@ -351,17 +635,91 @@ fn texture_index(
     }
 
     let texture_index = textures.len();
-    textures.push(json!({
-        "sampler": sampler_index,
-        "source": image_index
-    }));
-    texture_map.insert(image_index, texture_index);
+    let texture_json = match (image.mime_type.as_str(), fallback_index) {
+        ("image/ktx2", _) => {
+            extensions_used.push("KHR_texture_basisu".to_string());
+            json!({
+                "sampler": sampler_index,
+                "extensions": { "KHR_texture_basisu": { "source": primary_index } }
+            })
+        }
+        ("image/webp", Some(fallback_index)) => {
+            extensions_used.push("EXT_texture_webp".to_string());
+            json!({
+                "sampler": sampler_index,
+                "source": fallback_index,
+                "extensions": { "EXT_texture_webp": { "source": primary_index } }
+            })
+        }
+        _ => json!({
+            "sampler": sampler_index,
+            "source": primary_index
+        }),
+    };
+    textures.push(texture_json);
+    texture_map.insert(texture_key, texture_index);
     Ok(Some(TextureRef {
         texture_index,
         has_alpha,
     }))
 }
 
+fn register_image(
+    bytes: &[u8],
+    mime_type: &str,
+    has_alpha: bool,
+    texture_mode: &mut TextureMode,
+    images: &mut Vec<ImageEntry>,
+    image_map: &mut HashMap<u64, usize>,
+) -> Result<usize> {
+    let hash = hash_bytes(bytes);
+    if let Some(existing) = image_map.get(&hash) {
+        return Ok(*existing);
+    }
+
+    let entry = match texture_mode {
+        TextureMode::Embed => ImageEntry::Embedded(ImageData {
+            bytes: bytes.to_vec(),
+            mime_type: mime_type.to_string(),
+            has_alpha,
+            fallback: None,
+        }),
+        TextureMode::External(cache) => {
+            let ext = match mime_type {
+                "image/png" => "png",
+                "image/ktx2" => "ktx2",
+                "image/webp" => "webp",
+                _ => "jpg",
+            };
+            let filename = cache
+                .map
+                .entry(hash)
+                .or_insert_with(|| format!("tex_{hash:016x}.{ext}"))
+                .clone();
+            let path = cache.dir.join(&filename);
+            if !path.exists() {
+                fs::write(&path, bytes)
+                    .with_context(|| format!("write texture {}", path.display()))?;
+            }
+            let prefix = cache.uri_prefix.trim_end_matches('/');
+            let uri = if prefix.is_empty() {
+                filename
+            } else {
+                format!("{}/{}", prefix, filename)
+            };
+            ImageEntry::External {
+                uri,
+                mime_type: mime_type.to_string(),
+                has_alpha,
+            }
+        }
+    };
+    let idx = images.len();
+    images.push(entry);
+    image_map.insert(hash, idx);
+    Ok(idx)
+}
+
 fn hash_bytes(bytes: &[u8]) -> u64 {
     let mut hasher = DefaultHasher::new();
     bytes.hash(&mut hasher);
@@ -389,6 +747,59 @@ fn ensure_colors(vertex_count: usize, colors: &[f32]) -> Vec<f32> {
     vec![1.0; vertex_count * 4]
 }
 
+fn ensure_normals_indexed(positions: &[f32], normals: &[f32], indices: &[u32]) -> Vec<f32> {
+    if normals.len() == positions.len() && !normals.is_empty() {
+        return normals.to_vec();
+    }
+    generate_smooth_normals_indexed(positions, indices)
+}
+
+/// Like `generate_flat_normals`, but for an indexed part: a vertex can be
+/// shared by several triangles, so each face normal is accumulated into
+/// every one of its three vertices and the sum is normalized afterward,
+/// producing the usual smooth/averaged normal instead of a flat per-face one.
+fn generate_smooth_normals_indexed(positions: &[f32], indices: &[u32]) -> Vec<f32> {
+    let vertex_count = positions.len() / 3;
+    let mut normals = vec![0.0f32; vertex_count * 3];
+
+    for tri in indices.chunks_exact(3) {
+        let (a, b, c) = (tri[0] as usize, tri[1] as usize, tri[2] as usize);
+        let p0 = vec3_from_slice(positions, a * 3);
+        let p1 = vec3_from_slice(positions, b * 3);
+        let p2 = vec3_from_slice(positions, c * 3);
+
+        let e1 = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+        let e2 = [p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]];
+        let n = [
+            e1[1] * e2[2] - e1[2] * e2[1],
+            e1[2] * e2[0] - e1[0] * e2[2],
+            e1[0] * e2[1] - e1[1] * e2[0],
+        ];
+
+        for &v in &[a, b, c] {
+            normals[v * 3] += n[0];
+            normals[v * 3 + 1] += n[1];
+            normals[v * 3 + 2] += n[2];
+        }
+    }
+
+    for v in 0..vertex_count {
+        let n = [normals[v * 3], normals[v * 3 + 1], normals[v * 3 + 2]];
+        let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        if len > f32::EPSILON {
+            normals[v * 3] = n[0] / len;
+            normals[v * 3 + 1] = n[1] / len;
+            normals[v * 3 + 2] = n[2] / len;
+        } else {
+            normals[v * 3] = 0.0;
+            normals[v * 3 + 1] = 1.0;
+            normals[v * 3 + 2] = 0.0;
+        }
+    }
+
+    normals
+}
+
 fn generate_flat_normals(positions: &[f32]) -> Vec<f32> {
     let vertex_count = positions.len() / 3;
     let mut normals = vec![0.0f32; vertex_count * 3];
@@ -482,6 +893,75 @@ fn compute_tangents(positions: &[f32], uvs: &[f32], normals: &[f32]) -> Vec<f32>
     tangents
 }
 
+/// Like `compute_tangents`, but for an indexed part: accumulates each
+/// triangle's tangent/bitangent into all three of its (possibly shared)
+/// vertices, then orthonormalizes the per-vertex sum against that vertex's
+/// normal once every triangle has contributed.
+fn compute_tangents_indexed(positions: &[f32], uvs: &[f32], normals: &[f32], indices: &[u32]) -> Vec<f32> {
+    let vertex_count = positions.len() / 3;
+    if vertex_count == 0 {
+        return Vec::new();
+    }
+
+    let mut accum_tangent = vec![[0.0f32; 3]; vertex_count];
+    let mut accum_bitangent = vec![[0.0f32; 3]; vertex_count];
+
+    for tri in indices.chunks_exact(3) {
+        let (a, b, c) = (tri[0] as usize, tri[1] as usize, tri[2] as usize);
+        let p0 = vec3_from_slice(positions, a * 3);
+        let p1 = vec3_from_slice(positions, b * 3);
+        let p2 = vec3_from_slice(positions, c * 3);
+
+        let uv0 = vec2_from_slice(uvs, a * 2);
+        let uv1 = vec2_from_slice(uvs, b * 2);
+        let uv2 = vec2_from_slice(uvs, c * 2);
+
+        let edge1 = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+        let edge2 = [p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]];
+
+        let delta_uv1 = [uv1[0] - uv0[0], uv1[1] - uv0[1]];
+        let delta_uv2 = [uv2[0] - uv0[0], uv2[1] - uv0[1]];
+
+        let denom = delta_uv1[0] * delta_uv2[1] - delta_uv1[1] * delta_uv2[0];
+        let (tangent, bitangent) = if denom.abs() > f32::EPSILON {
+            let r = 1.0 / denom;
+            let tangent = [
+                (edge1[0] * delta_uv2[1] - edge2[0] * delta_uv1[1]) * r,
+                (edge1[1] * delta_uv2[1] - edge2[1] * delta_uv1[1]) * r,
+                (edge1[2] * delta_uv2[1] - edge2[2] * delta_uv1[1]) * r,
+            ];
+            let bitangent = [
+                (edge2[0] * delta_uv1[0] - edge1[0] * delta_uv2[0]) * r,
+                (edge2[1] * delta_uv1[0] - edge1[1] * delta_uv2[0]) * r,
+                (edge2[2] * delta_uv1[0] - edge1[2] * delta_uv2[0]) * r,
+            ];
+            (tangent, bitangent)
+        } else {
+            ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0])
+        };
+
+        for &v in &[a, b, c] {
+            for i in 0..3 {
+                accum_tangent[v][i] += tangent[i];
+                accum_bitangent[v][i] += bitangent[i];
+            }
+        }
+    }
+
+    let mut tangents = vec![0.0f32; vertex_count * 4];
+    for v in 0..vertex_count {
+        let normal = vec3_from_slice(normals, v * 3);
+        let t = orthonormalize(normal, accum_tangent[v]);
+        let w = handedness(normal, t, accum_bitangent[v]);
+        tangents[v * 4] = t[0];
+        tangents[v * 4 + 1] = t[1];
+        tangents[v * 4 + 2] = t[2];
+        tangents[v * 4 + 3] = w;
+    }
+
+    tangents
+}
+
 fn vec2_from_slice(data: &[f32], start: usize) -> [f32; 2] {
     if data.len() >= start + 2 {
         [data[start], data[start + 1]]
@@ -552,6 +1032,28 @@ fn push_accessor_vec3(
 }
 
 
+fn push_accessor_indices(
+    buffer: &mut BufferBuilder,
+    buffer_views: &mut Vec<Value>,
+    accessors: &mut Vec<Value>,
+    indices: &[u32],
+) -> Result<usize> {
+    let mut bytes = Vec::with_capacity(indices.len() * 4);
+    for value in indices {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    let (view_index, _) = buffer.push_bytes(buffer_views, &bytes, Some(TARGET_ELEMENT_ARRAY_BUFFER))?;
+    let accessor_index = accessors.len();
+    accessors.push(json!({
+        "bufferView": view_index,
+        "componentType": 5125,
+        "count": indices.len(),
+        "type": "SCALAR"
+    }));
+    Ok(accessor_index)
+}
+
+
 fn push_accessor_vec2(
     buffer: &mut BufferBuilder,
     buffer_views: &mut Vec<Value>,
@@ -592,6 +1094,97 @@ fn push_accessor_vec4(
 }
 
 
+/// Quantizes positions to normalized `u16` per component against `min`/
+/// `max`, the bounding box of the whole GLB (not just this part) so every
+/// part decodes correctly through the same node-level scale/translate.
+fn push_accessor_vec3_quantized(
+    buffer: &mut BufferBuilder,
+    buffer_views: &mut Vec<Value>,
+    accessors: &mut Vec<Value>,
+    data: &[f32],
+    min: [f32; 3],
+    max: [f32; 3],
+) -> Result<(usize, [f32; 3], [f32; 3])> {
+    let range = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+    let mut bytes = Vec::with_capacity(data.len() * 2);
+    for chunk in data.chunks(3) {
+        for i in 0..3 {
+            let normalized = if range[i] > 0.0 {
+                (chunk[i] - min[i]) / range[i]
+            } else {
+                0.0
+            };
+            let quant = (normalized.clamp(0.0, 1.0) * 65535.0).round() as u16;
+            bytes.extend_from_slice(&quant.to_le_bytes());
+        }
+    }
+    let (view_index, _) = buffer.push_bytes(buffer_views, &bytes, Some(TARGET_ARRAY_BUFFER))?;
+    let count = data.len() / 3;
+    let accessor_index = accessors.len();
+    accessors.push(json!({
+        "bufferView": view_index,
+        "componentType": 5123,
+        "normalized": true,
+        "count": count,
+        "type": "VEC3"
+    }));
+    let (part_min, part_max) = min_max_vec3(data);
+    Ok((accessor_index, part_min, part_max))
+}
+
+/// Quantizes unit-length normals to normalized `i8` per component. Plain
+/// byte-normalized VEC3 rather than octahedral, so it stays a spec-legal
+/// NORMAL accessor for viewers without bespoke decode support.
+fn push_accessor_vec3_normal_quantized(
+    buffer: &mut BufferBuilder,
+    buffer_views: &mut Vec<Value>,
+    accessors: &mut Vec<Value>,
+    data: &[f32],
+) -> Result<usize> {
+    let mut bytes = Vec::with_capacity(data.len());
+    for &value in data {
+        let quant = (value.clamp(-1.0, 1.0) * 127.0).round() as i8;
+        bytes.push(quant as u8);
+    }
+    let (view_index, _) = buffer.push_bytes(buffer_views, &bytes, Some(TARGET_ARRAY_BUFFER))?;
+    let count = data.len() / 3;
+    let accessor_index = accessors.len();
+    accessors.push(json!({
+        "bufferView": view_index,
+        "componentType": 5120,
+        "normalized": true,
+        "count": count,
+        "type": "VEC3"
+    }));
+    Ok(accessor_index)
+}
+
+/// Quantizes UVs (assumed unit range, like the rest of this pipeline) to
+/// normalized `u16` per component.
+fn push_accessor_vec2_quantized(
+    buffer: &mut BufferBuilder,
+    buffer_views: &mut Vec<Value>,
+    accessors: &mut Vec<Value>,
+    data: &[f32],
+) -> Result<usize> {
+    let mut bytes = Vec::with_capacity(data.len() * 2);
+    for &value in data {
+        let quant = (value.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        bytes.extend_from_slice(&quant.to_le_bytes());
+    }
+    let (view_index, _) = buffer.push_bytes(buffer_views, &bytes, Some(TARGET_ARRAY_BUFFER))?;
+    let count = data.len() / 2;
+    let accessor_index = accessors.len();
+    accessors.push(json!({
+        "bufferView": view_index,
+        "componentType": 5123,
+        "normalized": true,
+        "count": count,
+        "type": "VEC2"
+    }));
+    Ok(accessor_index)
+}
+
 fn update_accessor_bounds(accessor: &mut Value, min: [f32; 3], max: [f32; 3]) {
     accessor["min"] = json!(min);
     accessor["max"] = json!(max);
@@ -677,7 +1270,13 @@ impl BufferBuilder {
 }
 
 
-fn write_glb_container(path: &Path, gltf: Value, mut bin: Vec<u8>) -> Result<()> {
+fn write_glb_container(
+    path: &Path,
+    gltf: Value,
+    mut bin: Vec<u8>,
+    tile_compression: TileCompression,
+    compression_level: u32,
+) -> Result<()> {
     let mut json_bytes = serde_json::to_vec(&gltf)?;
     pad_bytes(&mut json_bytes, 0x20);
 
@@ -685,21 +1284,44 @@ fn write_glb_container(path: &Path, gltf: Value, mut bin: Vec<u8>) -> Result<()>
 
     let total_length = 12 + 8 + json_bytes.len() + 8 + bin.len();
 
-    let mut file = File::create(path)
-        .with_context(|| format!("open output file {}", path.display()))?;
+    let mut glb_bytes = Vec::with_capacity(total_length);
+    glb_bytes.extend_from_slice(&GLTF_MAGIC.to_le_bytes());
+    glb_bytes.extend_from_slice(&GLTF_VERSION.to_le_bytes());
+    glb_bytes.extend_from_slice(&(total_length as u32).to_le_bytes());
 
-    file.write_all(&GLTF_MAGIC.to_le_bytes())?;
-    file.write_all(&GLTF_VERSION.to_le_bytes())?;
-    file.write_all(&(total_length as u32).to_le_bytes())?;
+    glb_bytes.extend_from_slice(&(json_bytes.len() as u32).to_le_bytes());
+    glb_bytes.extend_from_slice(&CHUNK_TYPE_JSON.to_le_bytes());
+    glb_bytes.extend_from_slice(&json_bytes);
 
-    file.write_all(&(json_bytes.len() as u32).to_le_bytes())?;
-    file.write_all(&CHUNK_TYPE_JSON.to_le_bytes())?;
-    file.write_all(&json_bytes)?;
+    glb_bytes.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb_bytes.extend_from_slice(&CHUNK_TYPE_BIN.to_le_bytes());
+    glb_bytes.extend_from_slice(&bin);
 
-    file.write_all(&(bin.len() as u32).to_le_bytes())?;
-    file.write_all(&CHUNK_TYPE_BIN.to_le_bytes())?;
-    file.write_all(&bin)?;
+    write_compressed_file(path, &glb_bytes, tile_compression, compression_level)
+}
 
+/// Writes `bytes` to `path`, or, under `TileCompression::Gzip`, gzip-encodes
+/// them at `compression_level` (0-9) and writes the compressed bytes to
+/// `path` instead. See `TileCompression` for why `path` is never renamed.
+pub fn write_compressed_file(
+    path: &Path,
+    bytes: &[u8],
+    tile_compression: TileCompression,
+    compression_level: u32,
+) -> Result<()> {
+    match tile_compression {
+        TileCompression::None => {
+            fs::write(path, bytes).with_context(|| format!("write {}", path.display()))?;
+        }
+        TileCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::new(compression_level));
+            encoder.write_all(bytes)?;
+            let compressed = encoder
+                .finish()
+                .with_context(|| format!("gzip {}", path.display()))?;
+            fs::write(path, compressed).with_context(|| format!("write {}", path.display()))?;
+        }
+    }
     Ok(())
 }
 
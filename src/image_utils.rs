@@ -1,30 +1,190 @@
 use crate::ufbx_loader::TextureSource;
 use anyhow::{Context, Result};
+use basis_universal::{BasisTextureFormat, Compressor, CompressorParams};
 use image::{DynamicImage, ImageFormat};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io::Cursor;
 use std::path::Path;
 
+/// Output format for encoded textures. `Auto` keeps the historical PNG
+/// (alpha) / JPEG (opaque) behavior so existing callers see no change.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureFormat {
+    #[default]
+    Auto,
+    Ktx2Uastc,
+    Ktx2Etc1s,
+    WebP,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextureOptions {
+    pub format: TextureFormat,
+    /// Longest edge an output texture may have; 0 disables clamping.
+    pub max_texture_size: u32,
+    /// Round clamped dimensions down to the nearest power of two. Only the
+    /// Basis/KTX2 encode honors it; PNG/JPEG/WebP output keeps its clamped
+    /// dimensions.
+    pub pow2_snap: bool,
+    /// Try an 8-bit indexed-color PNG before falling back to full color,
+    /// for the `Auto` format and the `WebP` format's PNG/JPEG fallback.
+    pub quantize: bool,
+    /// Largest acceptable mean per-channel error (0-255 scale) before a
+    /// quantized encode is rejected in favor of the full-color one.
+    pub quantize_max_error: f64,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            format: TextureFormat::default(),
+            max_texture_size: 2048,
+            pow2_snap: false,
+            quantize: false,
+            quantize_max_error: 6.0,
+        }
+    }
+}
+
+/// A PNG/JPEG encode of the same image, stored alongside a primary encode so
+/// readers without the matching glTF extension can still display something.
+pub struct TextureFallback {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
 pub struct ImageData {
     pub bytes: Vec<u8>,
     pub mime_type: String,
     pub has_alpha: bool,
+    pub fallback: Option<TextureFallback>,
+}
+
+/// Deduplicates encoded textures by a hash of their *decoded* pixel buffer,
+/// so `TextureSource::Embedded` bytes and `TextureSource::File` paths that
+/// resolve to pixel-identical images share a single [`ImageData`] encode.
+/// Callers index into `entries` with the stable index `encode_texture`
+/// returns rather than holding their own copy of the data.
+#[derive(Default)]
+pub struct ImageCache {
+    by_pixel_hash: HashMap<u64, usize>,
+    pub entries: Vec<ImageData>,
+}
+
+impl ImageCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
 }
 
-pub fn encode_texture(source: &TextureSource) -> Result<Option<ImageData>> {
+fn hash_pixels(image: &DynamicImage) -> u64 {
+    let rgba = image.to_rgba8();
+    let mut hasher = DefaultHasher::new();
+    rgba.dimensions().hash(&mut hasher);
+    rgba.as_raw().hash(&mut hasher);
+    hasher.finish()
+}
+
+fn decode_source_for_hash(source: &TextureSource) -> Option<DynamicImage> {
     match source {
-        TextureSource::Embedded { bytes, name } => encode_from_bytes(bytes, name.as_deref()),
-        TextureSource::File(path) => match encode_from_path(path) {
+        TextureSource::Embedded { bytes, name } => {
+            decode_from_bytes(bytes, name.as_deref()).ok().flatten()
+        }
+        TextureSource::File(path) => image::open(path).ok(),
+    }
+}
+
+/// Encodes `source` per `options`, returning a stable index into
+/// `cache.entries` for the glTF writer to reuse across every material that
+/// references a pixel-identical texture.
+pub fn encode_texture(
+    source: &TextureSource,
+    options: &TextureOptions,
+    cache: &mut ImageCache,
+) -> Result<Option<usize>> {
+    let Some(hash) = decode_source_for_hash(source).map(|image| hash_pixels(&image)) else {
+        return Ok(None);
+    };
+    if let Some(&index) = cache.by_pixel_hash.get(&hash) {
+        return Ok(Some(index));
+    }
+
+    let Some(image) = encode_texture_uncached(source, options)? else {
+        return Ok(None);
+    };
+    let index = cache.entries.len();
+    cache.entries.push(image);
+    cache.by_pixel_hash.insert(hash, index);
+    Ok(Some(index))
+}
+
+fn encode_texture_uncached(
+    source: &TextureSource,
+    options: &TextureOptions,
+) -> Result<Option<ImageData>> {
+    let decoded = match source {
+        TextureSource::Embedded { bytes, name } => {
+            return match options.format {
+                TextureFormat::Auto => encode_from_bytes(bytes, name.as_deref(), options),
+                TextureFormat::Ktx2Uastc | TextureFormat::Ktx2Etc1s | TextureFormat::WebP => {
+                    match decode_from_bytes(bytes, name.as_deref()) {
+                        Ok(Some(image)) => Ok(Some(encode_with_format(image, options)?)),
+                        Ok(None) => Ok(None),
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+        }
+        TextureSource::File(path) => path,
+    };
+
+    match options.format {
+        TextureFormat::Auto => match encode_from_path(decoded, options) {
             Ok(image) => Ok(Some(image)),
             Err(err) => {
-                eprintln!("warning: texture {} skipped: {err}", path.display());
+                eprintln!("warning: texture {} skipped: {err}", decoded.display());
                 Ok(None)
             }
         },
+        TextureFormat::Ktx2Uastc | TextureFormat::Ktx2Etc1s | TextureFormat::WebP => {
+            match image::open(decoded) {
+                Ok(image) => Ok(Some(encode_with_format(image, options)?)),
+                Err(err) => {
+                    eprintln!("warning: texture {} skipped: {err}", decoded.display());
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+fn encode_with_format(image: DynamicImage, options: &TextureOptions) -> Result<ImageData> {
+    match options.format {
+        TextureFormat::Ktx2Uastc | TextureFormat::Ktx2Etc1s => encode_ktx2(image, options),
+        TextureFormat::WebP => encode_webp(image, options),
+        TextureFormat::Auto => encode_image(image, options),
     }
 }
 
-fn encode_from_path(path: &Path) -> Result<ImageData> {
+fn exceeds_max_size(image: &DynamicImage, max_texture_size: u32) -> bool {
+    max_texture_size > 0 && image.width().max(image.height()) > max_texture_size
+}
+
+fn resize_to_max_dimension(image: DynamicImage, max_texture_size: u32) -> DynamicImage {
+    if !exceeds_max_size(&image, max_texture_size) {
+        return image;
+    }
+    let longest = image.width().max(image.height()) as f32;
+    let scale = max_texture_size as f32 / longest;
+    let new_width = ((image.width() as f32 * scale).round() as u32).max(1);
+    let new_height = ((image.height() as f32 * scale).round() as u32).max(1);
+    image.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
+}
+
+fn encode_from_path(path: &Path, options: &TextureOptions) -> Result<ImageData> {
     let ext = path
         .extension()
         .and_then(|v| v.to_str())
@@ -34,58 +194,77 @@ fn encode_from_path(path: &Path) -> Result<ImageData> {
     if ext == "png" || ext == "jpg" || ext == "jpeg" {
         let bytes =
             fs::read(path).with_context(|| format!("read texture {}", path.display()))?;
-        if ext == "png" {
-            let image = image::load_from_memory_with_format(&bytes, ImageFormat::Png)
-                .with_context(|| format!("decode texture {}", path.display()))?;
+        let format = if ext == "png" {
+            ImageFormat::Png
+        } else {
+            ImageFormat::Jpeg
+        };
+        let image = image::load_from_memory_with_format(&bytes, format)
+            .with_context(|| format!("decode texture {}", path.display()))?;
+        if !exceeds_max_size(&image, options.max_texture_size) && !options.quantize {
+            let has_alpha = if format == ImageFormat::Png {
+                image.color().has_alpha()
+            } else {
+                false
+            };
+            let mime_type = if format == ImageFormat::Png {
+                "image/png"
+            } else {
+                "image/jpeg"
+            };
             return Ok(ImageData {
                 bytes,
-                mime_type: "image/png".to_string(),
-                has_alpha: image.color().has_alpha(),
+                mime_type: mime_type.to_string(),
+                has_alpha,
+                fallback: None,
             });
         }
-        return Ok(ImageData {
-            bytes,
-            mime_type: "image/jpeg".to_string(),
-            has_alpha: false,
-        });
+        return encode_image(image, options);
     }
 
     let image = image::open(path)
         .with_context(|| format!("decode texture {}", path.display()))?;
-    encode_image(image)
+    encode_image(image, options)
 }
 
-fn encode_from_bytes(bytes: &[u8], name: Option<&str>) -> Result<Option<ImageData>> {
+fn encode_from_bytes(
+    bytes: &[u8],
+    name: Option<&str>,
+    options: &TextureOptions,
+) -> Result<Option<ImageData>> {
     if let Ok(format) = image::guess_format(bytes) {
-        if format == ImageFormat::Png {
-            if let Ok(image) = image::load_from_memory_with_format(bytes, ImageFormat::Png) {
-                return Ok(Some(ImageData {
-                    bytes: bytes.to_vec(),
-                    mime_type: "image/png".to_string(),
-                    has_alpha: image.color().has_alpha(),
-                }));
+        if format == ImageFormat::Png || format == ImageFormat::Jpeg {
+            if let Ok(image) = image::load_from_memory_with_format(bytes, format) {
+                if !exceeds_max_size(&image, options.max_texture_size) && !options.quantize {
+                    let has_alpha = format == ImageFormat::Png && image.color().has_alpha();
+                    let mime_type = if format == ImageFormat::Png {
+                        "image/png"
+                    } else {
+                        "image/jpeg"
+                    };
+                    return Ok(Some(ImageData {
+                        bytes: bytes.to_vec(),
+                        mime_type: mime_type.to_string(),
+                        has_alpha,
+                        fallback: None,
+                    }));
+                }
+                return Ok(Some(encode_image(image, options)?));
             }
         }
-        if format == ImageFormat::Jpeg {
-            return Ok(Some(ImageData {
-                bytes: bytes.to_vec(),
-                mime_type: "image/jpeg".to_string(),
-                has_alpha: false,
-            }));
-        }
         if let Ok(image) = image::load_from_memory_with_format(bytes, format) {
-            return Ok(Some(encode_image(image)?));
+            return Ok(Some(encode_image(image, options)?));
         }
     }
 
     if let Some(format) = name.and_then(format_from_name) {
         if let Ok(image) = image::load_from_memory_with_format(bytes, format) {
-            return Ok(Some(encode_image(image)?));
+            return Ok(Some(encode_image(image, options)?));
         }
     }
 
     match image::load_from_memory(bytes) {
-        Ok(image) => Ok(Some(encode_image(image)?)),
+        Ok(image) => Ok(Some(encode_image(image, options)?)),
         Err(err) => {
             if let Some(name) = name {
                 eprintln!("warning: could not decode embedded texture {name}: {err}");
@@ -102,8 +281,218 @@ fn format_from_name(name: &str) -> Option<ImageFormat> {
     ImageFormat::from_extension(ext)
 }
 
-fn encode_image(image: DynamicImage) -> Result<ImageData> {
+fn decode_from_bytes(bytes: &[u8], name: Option<&str>) -> Result<Option<DynamicImage>> {
+    if let Ok(format) = image::guess_format(bytes) {
+        if let Ok(image) = image::load_from_memory_with_format(bytes, format) {
+            return Ok(Some(image));
+        }
+    }
+
+    if let Some(format) = name.and_then(format_from_name) {
+        if let Ok(image) = image::load_from_memory_with_format(bytes, format) {
+            return Ok(Some(image));
+        }
+    }
+
+    match image::load_from_memory(bytes) {
+        Ok(image) => Ok(Some(image)),
+        Err(err) => {
+            if let Some(name) = name {
+                eprintln!("warning: could not decode embedded texture {name}: {err}");
+            } else {
+                eprintln!("warning: could not decode embedded texture: {err}");
+            }
+            Ok(None)
+        }
+    }
+}
+
+// Basis requires every mip to be aligned to a 4x4 block; dimensions are
+// rounded up rather than down, and the image is resampled to the rounded size
+// so no source rows or columns are cropped.
+fn round_up_to_block(value: u32) -> u32 {
+    (value + 3) & !3
+}
+
+// Round down to the nearest power of two (minimum 1), as required when
+// `pow2_snap` is set for mip-chain generation.
+fn floor_pow2(value: u32) -> u32 {
+    if value <= 1 {
+        1
+    } else {
+        1u32 << (31 - value.leading_zeros())
+    }
+}
+
+fn box_downsample(image: &image::RgbaImage) -> image::RgbaImage {
+    let (width, height) = image.dimensions();
+    let new_width = (width / 2).max(1);
+    let new_height = (height / 2).max(1);
+    let mut out = image::RgbaImage::new(new_width, new_height);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let x0 = (x * 2).min(width - 1);
+            let x1 = (x * 2 + 1).min(width - 1);
+            let y0 = (y * 2).min(height - 1);
+            let y1 = (y * 2 + 1).min(height - 1);
+            let mut sum = [0u32; 4];
+            for (px, py) in [(x0, y0), (x1, y0), (x0, y1), (x1, y1)] {
+                let pixel = image.get_pixel(px, py);
+                for c in 0..4 {
+                    sum[c] += pixel.0[c] as u32;
+                }
+            }
+            out.put_pixel(
+                x,
+                y,
+                image::Rgba([
+                    (sum[0] / 4) as u8,
+                    (sum[1] / 4) as u8,
+                    (sum[2] / 4) as u8,
+                    (sum[3] / 4) as u8,
+                ]),
+            );
+        }
+    }
+    out
+}
+
+fn generate_mip_chain(base: &image::RgbaImage) -> Vec<image::RgbaImage> {
+    let mut levels = vec![base.clone()];
+    while {
+        let (w, h) = levels.last().unwrap().dimensions();
+        w > 1 || h > 1
+    } {
+        let next = box_downsample(levels.last().unwrap());
+        levels.push(next);
+    }
+    levels
+}
+
+fn encode_ktx2(image: DynamicImage, options: &TextureOptions) -> Result<ImageData> {
+    let image = resize_to_max_dimension(image, options.max_texture_size);
     let has_alpha = image.color().has_alpha();
+    let rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    let (target_width, target_height) = if options.pow2_snap {
+        (floor_pow2(width), floor_pow2(height))
+    } else {
+        (width, height)
+    };
+    let (block_width, block_height) = (
+        round_up_to_block(target_width),
+        round_up_to_block(target_height),
+    );
+
+    let base = if (target_width, target_height) != (width, height) {
+        image::imageops::resize(
+            &rgba,
+            target_width,
+            target_height,
+            image::imageops::FilterType::Lanczos3,
+        )
+    } else {
+        rgba
+    };
+    let mip_chain = generate_mip_chain(&base);
+
+    let mut params = CompressorParams::new();
+    params.set_generate_mipmaps(false);
+    let basis_format = match options.format {
+        TextureFormat::Ktx2Uastc => BasisTextureFormat::UASTC4x4,
+        TextureFormat::Ktx2Etc1s => BasisTextureFormat::ETC1S,
+        TextureFormat::Auto | TextureFormat::WebP => {
+            unreachable!("encode_ktx2 only runs for ktx2 formats")
+        }
+    };
+    params.set_basis_format(basis_format);
+
+    for (level, mip) in mip_chain.iter().enumerate() {
+        let (mip_width, mip_height) = mip.dimensions();
+        let (aligned_width, aligned_height) = if level == 0 {
+            (block_width, block_height)
+        } else {
+            (
+                round_up_to_block(mip_width),
+                round_up_to_block(mip_height),
+            )
+        };
+        let aligned = if (aligned_width, aligned_height) != (mip_width, mip_height) {
+            image::imageops::resize(
+                mip,
+                aligned_width,
+                aligned_height,
+                image::imageops::FilterType::Triangle,
+            )
+        } else {
+            mip.clone()
+        };
+        params
+            .source_image_mut(level as u32)
+            .init(&aligned, aligned_width, aligned_height, 4);
+    }
+
+    let mut compressor = unsafe { Compressor::new(num_cpus::get() as u32) };
+    unsafe {
+        compressor.init(&params);
+        compressor
+            .process()
+            .map_err(|err| anyhow::anyhow!("basis universal compression failed: {err:?}"))?;
+    }
+    let ktx2_bytes = unsafe { compressor.ktx2_file().to_vec() };
+
+    Ok(ImageData {
+        bytes: ktx2_bytes,
+        mime_type: "image/ktx2".to_string(),
+        has_alpha,
+        fallback: None,
+    })
+}
+
+fn encode_webp(image: DynamicImage, options: &TextureOptions) -> Result<ImageData> {
+    let image = resize_to_max_dimension(image, options.max_texture_size);
+    let has_alpha = image.color().has_alpha();
+    let rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    let encoder = webp::Encoder::from_rgba(&rgba, width, height);
+    let webp_bytes = if has_alpha {
+        encoder.encode_lossless()
+    } else {
+        encoder.encode(80.0)
+    }
+    .to_vec();
+
+    let fallback = encode_image(image, options)?;
+
+    Ok(ImageData {
+        bytes: webp_bytes,
+        mime_type: "image/webp".to_string(),
+        has_alpha,
+        fallback: Some(TextureFallback {
+            bytes: fallback.bytes,
+            mime_type: fallback.mime_type,
+        }),
+    })
+}
+
+fn encode_image(image: DynamicImage, options: &TextureOptions) -> Result<ImageData> {
+    let image = resize_to_max_dimension(image, options.max_texture_size);
+    let has_alpha = image.color().has_alpha();
+
+    if options.quantize {
+        let rgba = image.to_rgba8();
+        if let Some(bytes) = quantize_to_png(&rgba, options.quantize_max_error)? {
+            return Ok(ImageData {
+                bytes,
+                mime_type: "image/png".to_string(),
+                has_alpha,
+                fallback: None,
+            });
+        }
+    }
+
     let format = if has_alpha {
         ImageFormat::Png
     } else {
@@ -123,5 +512,221 @@ fn encode_image(image: DynamicImage) -> Result<ImageData> {
         bytes: data,
         mime_type: mime_type.to_string(),
         has_alpha,
+        fallback: None,
     })
 }
+
+// A median-cut color box: the set of distinct colors it owns plus their
+// total pixel weight, used both to pick the next box to split and to derive
+// the final palette entry (population-weighted average).
+struct ColorBox {
+    colors: Vec<([u8; 4], u64)>,
+}
+
+impl ColorBox {
+    fn channel_range(&self, channel: usize) -> (u8, u8) {
+        let mut lo = 255u8;
+        let mut hi = 0u8;
+        for (color, _) in &self.colors {
+            lo = lo.min(color[channel]);
+            hi = hi.max(color[channel]);
+        }
+        (lo, hi)
+    }
+
+    fn widest_channel(&self) -> usize {
+        (0..4)
+            .max_by_key(|&c| {
+                let (lo, hi) = self.channel_range(c);
+                hi - lo
+            })
+            .unwrap_or(0)
+    }
+
+    fn weighted_average(&self) -> [u8; 4] {
+        let mut sum = [0u64; 4];
+        let mut total = 0u64;
+        for (color, weight) in &self.colors {
+            for c in 0..4 {
+                sum[c] += color[c] as u64 * weight;
+            }
+            total += weight;
+        }
+        if total == 0 {
+            return [0, 0, 0, 255];
+        }
+        [
+            (sum[0] / total) as u8,
+            (sum[1] / total) as u8,
+            (sum[2] / total) as u8,
+            (sum[3] / total) as u8,
+        ]
+    }
+}
+
+// Median-cut quantization: repeatedly split the color box with the widest
+// channel range at its weighted median until we have `max_colors` boxes,
+// then take each box's weighted-average color as a palette entry.
+fn median_cut_palette(histogram: &[([u8; 4], u64)], max_colors: usize) -> Vec<[u8; 4]> {
+    let mut boxes = vec![ColorBox {
+        colors: histogram.to_vec(),
+    }];
+
+    while boxes.len() < max_colors {
+        let Some(split_index) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .max_by_key(|(_, b)| {
+                let channel = b.widest_channel();
+                let (lo, hi) = b.channel_range(channel);
+                hi - lo
+            })
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+
+        let target = boxes.swap_remove(split_index);
+        let channel = target.widest_channel();
+        let mut colors = target.colors;
+        colors.sort_by_key(|(color, _)| color[channel]);
+
+        let total_weight: u64 = colors.iter().map(|(_, w)| w).sum();
+        let mut running = 0u64;
+        let mut split_at = colors.len() / 2;
+        for (i, (_, weight)) in colors.iter().enumerate() {
+            running += weight;
+            if running * 2 >= total_weight {
+                split_at = (i + 1).min(colors.len() - 1).max(1);
+                break;
+            }
+        }
+
+        let second = colors.split_off(split_at);
+        boxes.push(ColorBox { colors });
+        boxes.push(ColorBox { colors: second });
+    }
+
+    boxes.iter().map(|b| b.weighted_average()).collect()
+}
+
+fn nearest_palette_index(palette: &[[u8; 4]], color: [u8; 4]) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| {
+            let dr = entry[0] as i32 - color[0] as i32;
+            let dg = entry[1] as i32 - color[1] as i32;
+            let db = entry[2] as i32 - color[2] as i32;
+            let da = entry[3] as i32 - color[3] as i32;
+            dr * dr + dg * dg + db * db + da * da
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+// Quantizes `rgba` to an 8-bit indexed-color palette (median-cut, <=256
+// entries) with Floyd-Steinberg dithering, and encodes it as an indexed PNG.
+// Returns `Ok(None)` if the resulting mean per-channel error exceeds
+// `max_error`, in which case the caller should fall back to full color.
+fn quantize_to_png(rgba: &image::RgbaImage, max_error: f64) -> Result<Option<Vec<u8>>> {
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return Ok(None);
+    }
+
+    let mut histogram: std::collections::HashMap<[u8; 4], u64> = std::collections::HashMap::new();
+    for pixel in rgba.pixels() {
+        *histogram.entry(pixel.0).or_insert(0) += 1;
+    }
+    let samples: Vec<([u8; 4], u64)> = histogram.into_iter().collect();
+    if samples.len() <= 256 {
+        return encode_indexed_png(rgba, &samples.iter().map(|(c, _)| *c).collect::<Vec<_>>());
+    }
+
+    let palette = median_cut_palette(&samples, 256);
+
+    // Dither in floating point so accumulated error doesn't clip per pixel.
+    let mut work: Vec<[f32; 4]> = rgba.pixels().map(|p| p.0.map(|v| v as f32)).collect();
+    let mut total_error = 0.0f64;
+    let mut indices = vec![0u8; (width * height) as usize];
+
+    for y in 0..height {
+        for x in 0..width {
+            let idx = (y * width + x) as usize;
+            let current = work[idx].map(|v| v.round().clamp(0.0, 255.0) as u8);
+            let palette_index = nearest_palette_index(&palette, current);
+            let chosen = palette[palette_index];
+            indices[idx] = palette_index as u8;
+
+            for c in 0..4 {
+                let error = current[c] as f32 - chosen[c] as f32;
+                total_error += error.abs() as f64;
+                let mut distribute = |dx: i64, dy: i64, weight: f32| {
+                    let nx = x as i64 + dx;
+                    let ny = y as i64 + dy;
+                    if nx >= 0 && nx < width as i64 && ny >= 0 && ny < height as i64 {
+                        let nidx = (ny as u32 * width + nx as u32) as usize;
+                        work[nidx][c] += error * weight;
+                    }
+                };
+                distribute(1, 0, 7.0 / 16.0);
+                distribute(-1, 1, 3.0 / 16.0);
+                distribute(0, 1, 5.0 / 16.0);
+                distribute(1, 1, 1.0 / 16.0);
+            }
+        }
+    }
+
+    let mean_error = total_error / (width as f64 * height as f64 * 4.0);
+    if mean_error > max_error {
+        return Ok(None);
+    }
+
+    encode_indexed_png_with_indices(width, height, &palette, &indices)
+}
+
+fn encode_indexed_png(rgba: &image::RgbaImage, palette: &[[u8; 4]]) -> Result<Option<Vec<u8>>> {
+    let (width, height) = rgba.dimensions();
+    let mut indices = vec![0u8; (width * height) as usize];
+    for (i, pixel) in rgba.pixels().enumerate() {
+        indices[i] = nearest_palette_index(palette, pixel.0) as u8;
+    }
+    encode_indexed_png_with_indices(width, height, palette, &indices)
+}
+
+fn encode_indexed_png_with_indices(
+    width: u32,
+    height: u32,
+    palette: &[[u8; 4]],
+    indices: &[u8],
+) -> Result<Option<Vec<u8>>> {
+    let mut rgb_palette = Vec::with_capacity(palette.len() * 3);
+    let mut alpha_palette = Vec::with_capacity(palette.len());
+    let mut any_transparent = false;
+    for color in palette {
+        rgb_palette.extend_from_slice(&color[0..3]);
+        alpha_palette.push(color[3]);
+        any_transparent |= color[3] != 255;
+    }
+
+    let mut data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut data, width, height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(rgb_palette);
+        if any_transparent {
+            encoder.set_trns(alpha_palette);
+        }
+        let mut writer = encoder
+            .write_header()
+            .context("write indexed PNG header")?;
+        writer
+            .write_image_data(indices)
+            .context("write indexed PNG data")?;
+    }
+
+    Ok(Some(data))
+}
@@ -1,24 +1,139 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+mod bvh;
 mod geo;
 mod gltf_writer;
 mod image_utils;
+mod obj_loader;
+mod scene_loader;
+mod simplify;
 mod tiles;
+mod ufbx_bindgen;
 mod ufbx_loader;
 mod ufbx_sys;
 
+use image_utils::TextureOptions;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum TextureFormatArg {
+    Auto,
+    Ktx2Uastc,
+    Ktx2Etc1s,
+    Webp,
+}
+
+impl From<TextureFormatArg> for image_utils::TextureFormat {
+    fn from(value: TextureFormatArg) -> Self {
+        match value {
+            TextureFormatArg::Auto => image_utils::TextureFormat::Auto,
+            TextureFormatArg::Ktx2Uastc => image_utils::TextureFormat::Ktx2Uastc,
+            TextureFormatArg::Ktx2Etc1s => image_utils::TextureFormat::Ktx2Etc1s,
+            TextureFormatArg::Webp => image_utils::TextureFormat::WebP,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum CrsArg {
+    Enu,
+    Mercator,
+}
+
+impl From<CrsArg> for geo::Crs {
+    fn from(value: CrsArg) -> Self {
+        match value {
+            CrsArg::Enu => geo::Crs::Enu,
+            CrsArg::Mercator => geo::Crs::WebMercator,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum WeldMergeArg {
+    Average,
+    KeepFirst,
+}
+
+impl From<WeldMergeArg> for tiles::WeldMerge {
+    fn from(value: WeldMergeArg) -> Self {
+        match value {
+            WeldMergeArg::Average => tiles::WeldMerge::Average,
+            WeldMergeArg::KeepFirst => tiles::WeldMerge::KeepFirst,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum MeshCompressionArg {
+    None,
+    Quantized,
+}
+
+impl From<MeshCompressionArg> for gltf_writer::MeshCompression {
+    fn from(value: MeshCompressionArg) -> Self {
+        match value {
+            MeshCompressionArg::None => gltf_writer::MeshCompression::None,
+            MeshCompressionArg::Quantized => gltf_writer::MeshCompression::Quantized,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum TileCompressionArg {
+    None,
+    Gzip,
+}
+
+impl From<TileCompressionArg> for gltf_writer::TileCompression {
+    fn from(value: TileCompressionArg) -> Self {
+        match value {
+            TileCompressionArg::None => gltf_writer::TileCompression::None,
+            TileCompressionArg::Gzip => gltf_writer::TileCompression::Gzip,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(author, version, about)]
 struct Args {
-    /// Input FBX file path (gltf mode)
+    /// Input mesh file path (gltf mode): `.fbx` or `.obj` (with a companion `.mtl`)
     input: Option<PathBuf>,
     /// Output GLB file path (gltf mode)
     output: Option<PathBuf>,
     /// Disable V flip on UVs (default: flip V)
     #[arg(long)]
     no_flip_v: bool,
+    /// Bake the source scene's right/up axis convention into a geometry
+    /// transform instead of leaving it as unapplied metadata
+    #[arg(long)]
+    bake_axis_convention: bool,
+    /// Weld coincident vertices and emit an indexed mesh instead of a flat
+    /// triangle soup, shrinking the output buffer
+    #[arg(long)]
+    weld_vertices: bool,
+    /// Position quantization epsilon (in scene units) used by
+    /// `--weld-vertices`; vertices whose positions round to the same cell
+    /// of this grid merge
+    #[arg(long, default_value_t = 0.0001)]
+    weld_vertices_epsilon: f32,
+    /// Texture output format (default: auto PNG/JPEG passthrough)
+    #[arg(long, value_enum, default_value = "auto")]
+    texture_format: TextureFormatArg,
+    /// Longest edge an output texture may have; 0 disables clamping
+    #[arg(long, default_value_t = 2048)]
+    max_texture_size: u32,
+    /// Snap clamped texture dimensions down to the nearest power of two
+    /// (KTX2 output only)
+    #[arg(long)]
+    pow2_textures: bool,
+    /// Try an 8-bit indexed-color PNG before falling back to full color
+    #[arg(long)]
+    quantize_textures: bool,
+    /// Largest acceptable mean per-channel error (0-255) for quantized output
+    #[arg(long, default_value_t = 6.0)]
+    quantize_max_error: f64,
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -27,7 +142,7 @@ struct Args {
 enum Command {
     /// Export a 3D Tiles 1.1 tileset
     Tiles {
-        /// Input FBX file path
+        /// Input mesh file path: `.fbx` or `.obj` (with a companion `.mtl`)
         input: PathBuf,
         /// Output directory for tileset.json and tiles/
         output_dir: PathBuf,
@@ -61,6 +176,92 @@ enum Command {
         /// Disable V flip on UVs (default: flip V)
         #[arg(long)]
         no_flip_v: bool,
+        /// Bake the source scene's right/up axis convention into a geometry
+        /// transform instead of leaving it as unapplied metadata
+        #[arg(long)]
+        bake_axis_convention: bool,
+        /// Texture output format (default: auto PNG/JPEG passthrough)
+        #[arg(long, value_enum, default_value = "auto")]
+        texture_format: TextureFormatArg,
+        /// Longest edge an output texture may have; 0 disables clamping
+        #[arg(long, default_value_t = 2048)]
+        max_texture_size: u32,
+        /// Snap clamped texture dimensions down to the nearest power of two
+        /// (KTX2 output only)
+        #[arg(long)]
+        pow2_textures: bool,
+        /// Try an 8-bit indexed-color PNG before falling back to full color
+        #[arg(long)]
+        quantize_textures: bool,
+        /// Largest acceptable mean per-channel error (0-255) for quantized output
+        #[arg(long, default_value_t = 6.0)]
+        quantize_max_error: f64,
+        /// Georeferencing scheme: `enu` (default) or `mercator` to additionally
+        /// tag tiles with their slippy XYZ index
+        #[arg(long, value_enum, default_value = "enu")]
+        crs: CrsArg,
+        /// H3 resolution (0-15) to tag each tile's center with; omit to skip
+        /// H3 tagging entirely
+        #[arg(long)]
+        h3_res: Option<u8>,
+        /// Subdivide on Y as well as X/Z, producing true cube tiles instead
+        /// of full-height columns. Useful for tall or vertically dense scenes.
+        #[arg(long)]
+        octree: bool,
+        /// Extrude a thin downward skirt along each tile boundary edge to
+        /// hide cracks between mismatched LOD levels
+        #[arg(long)]
+        generate_skirts: bool,
+        /// Skirt depth as a fraction of each tile's own size
+        #[arg(long, default_value_t = 0.05)]
+        skirt_depth_ratio: f64,
+        /// Vertex attribute compression applied to each tile's GLB
+        #[arg(long, value_enum, default_value = "none")]
+        mesh_compression: MeshCompressionArg,
+        /// Repair vertex normals that collapsed to near-zero (or flipped)
+        /// during clipping by falling back to the triangle's face normal
+        #[arg(long)]
+        recompute_collapsed_normals: bool,
+        /// Scan each tile's geometry for non-finite positions/normals/uvs, zero-length
+        /// normals, and degenerate triangles before writing it, repairing
+        /// what it can and warning about what it found
+        #[arg(long)]
+        validate_and_repair_mesh: bool,
+        /// Weld vertices whose positions round to the same `weld-epsilon`
+        /// meter grid cell, to seal
+        /// T-junction cracks left by independent clip passes
+        #[arg(long)]
+        weld_tile_seams: bool,
+        /// Grid size for `--weld-tile-seams`, in meters
+        #[arg(long, default_value_t = 0.001)]
+        weld_epsilon: f64,
+        /// How `--weld-tile-seams` picks attributes for a merged vertex
+        #[arg(long, value_enum, default_value = "average")]
+        weld_merge: WeldMergeArg,
+        /// Build a part-level BVH (see the `bvh` module) instead of the
+        /// default triangle-grid pipeline, and emit its nodes as a nested
+        /// 3D Tiles tree with `box` bounding volumes
+        #[arg(long)]
+        bvh: bool,
+        /// Max parts per BVH leaf tile; ignored unless `--bvh` is set
+        #[arg(long, default_value_t = 8)]
+        bvh_max_parts_per_leaf: usize,
+        /// Gzip-compress each tile GLB and tileset.json in place, for hosts
+        /// that serve them with `Content-Encoding: gzip`
+        #[arg(long, value_enum, default_value = "none")]
+        tile_compression: TileCompressionArg,
+        /// flate2 compression level (0-9); ignored unless `--tile-compression gzip`
+        #[arg(long, default_value_t = 6)]
+        compression_level: u32,
+        /// Weld coincident vertices and emit an indexed mesh for each tile
+        /// instead of a flat triangle soup, shrinking the output buffer
+        #[arg(long)]
+        weld_vertices: bool,
+        /// Position quantization epsilon (in scene units) used by
+        /// `--weld-vertices`; vertices whose positions round to the same
+        /// cell of this grid merge
+        #[arg(long, default_value_t = 0.0001)]
+        weld_vertices_epsilon: f32,
     },
 }
 
@@ -81,12 +282,38 @@ fn main() -> Result<()> {
             max_level,
             embed_textures,
             no_flip_v,
+            bake_axis_convention,
+            texture_format,
+            max_texture_size,
+            pow2_textures,
+            quantize_textures,
+            quantize_max_error,
+            crs,
+            h3_res,
+            octree,
+            generate_skirts,
+            skirt_depth_ratio,
+            mesh_compression,
+            recompute_collapsed_normals,
+            validate_and_repair_mesh,
+            weld_tile_seams,
+            weld_epsilon,
+            weld_merge,
+            bvh,
+            bvh_max_parts_per_leaf,
+            tile_compression,
+            compression_level,
+            weld_vertices,
+            weld_vertices_epsilon,
         }) => {
-            let mut scene = ufbx_loader::load_scene(&input)
-                .with_context(|| format!("failed to load FBX: {}", input.display()))?;
+            let mut scene = scene_loader::load_scene(&input)
+                .with_context(|| format!("failed to load input mesh: {}", input.display()))?;
             if no_flip_v {
                 ufbx_loader::flip_v(&mut scene);
             }
+            if bake_axis_convention {
+                ufbx_loader::bake_axis_convention(&mut scene);
+            }
             let options = tiles::TilesetOptions {
                 origin_lat,
                 origin_lon,
@@ -97,10 +324,43 @@ fn main() -> Result<()> {
                 min_tile_size,
                 max_level,
                 embed_textures,
+                crs: crs.into(),
+                h3_resolution: h3_res,
+                subdivision: if octree {
+                    tiles::Subdivision::Octree
+                } else {
+                    tiles::Subdivision::Quadtree
+                },
+                generate_skirts,
+                skirt_depth_ratio,
+                recompute_collapsed_normals,
+                validate_and_repair_mesh,
+                weld_tile_seams,
+                weld_epsilon,
+                weld_merge: weld_merge.into(),
+                mesh_compression: mesh_compression.into(),
+                tile_compression: tile_compression.into(),
+                compression_level,
+                weld_vertices,
+                weld_vertices_epsilon,
+                texture_options: TextureOptions {
+                    format: texture_format.into(),
+                    max_texture_size,
+                    pow2_snap: pow2_textures,
+                    quantize: quantize_textures,
+                    quantize_max_error,
+                },
+                bvh_max_parts_per_leaf: Some(bvh_max_parts_per_leaf),
             };
-            tiles::export_tileset(&scene, &output_dir, &options).with_context(|| {
-                format!("failed to export tileset to {}", output_dir.display())
-            })?;
+            if bvh {
+                tiles::export_bvh_tileset(&scene, &output_dir, &options).with_context(|| {
+                    format!("failed to export BVH tileset to {}", output_dir.display())
+                })?;
+            } else {
+                tiles::export_tileset(&scene, &output_dir, &options).with_context(|| {
+                    format!("failed to export tileset to {}", output_dir.display())
+                })?;
+            }
         }
         None => {
             let input = args
@@ -109,12 +369,26 @@ fn main() -> Result<()> {
             let output = args
                 .output
                 .ok_or_else(|| anyhow::anyhow!("missing output path"))?;
-            let mut scene = ufbx_loader::load_scene(&input)
-                .with_context(|| format!("failed to load FBX: {}", input.display()))?;
+            let mut scene = scene_loader::load_scene(&input)
+                .with_context(|| format!("failed to load input mesh: {}", input.display()))?;
             if args.no_flip_v {
                 ufbx_loader::flip_v(&mut scene);
             }
-            gltf_writer::write_glb(&scene, &output)
+            if args.bake_axis_convention {
+                ufbx_loader::bake_axis_convention(&mut scene);
+            }
+            if args.weld_vertices {
+                ufbx_loader::weld_scene(&mut scene, args.weld_vertices_epsilon);
+            }
+            let texture_options = TextureOptions {
+                format: args.texture_format.into(),
+                max_texture_size: args.max_texture_size,
+                pow2_snap: args.pow2_textures,
+                quantize: args.quantize_textures,
+                quantize_max_error: args.quantize_max_error,
+            };
+            let mut mode = gltf_writer::TextureMode::Embed;
+            gltf_writer::write_glb_with_options(&scene, &output, &mut mode, &texture_options)
                 .with_context(|| format!("failed to write GLB: {}", output.display()))?;
         }
     }
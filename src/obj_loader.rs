@@ -0,0 +1,317 @@
+//! OBJ + MTL backend for `scene_loader::load_scene`. Produces the same
+//! `SceneData`/`Material`/`MeshPart` types `ufbx_loader` does, via a hand-
+//! rolled parser in the spirit of obj-rs's raw parser (positions/normals/
+//! texcoords plus `f`/`usemtl` face groups), so the rest of the pipeline
+//! (tiling, GLB writing) never needs to know which backend a scene came
+//! from.
+
+use crate::ufbx_loader::{AlphaMode, AxisDir, Material, MeshPart, SceneData, TextureSource};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+struct ObjMaterialDef {
+    name: String,
+    base_color: [f32; 4],
+    emissive: [f32; 3],
+    roughness: f32,
+    base_color_texture: Option<PathBuf>,
+    normal_texture: Option<PathBuf>,
+}
+
+impl ObjMaterialDef {
+    fn new(name: String) -> Self {
+        ObjMaterialDef {
+            name,
+            base_color: [1.0, 1.0, 1.0, 1.0],
+            emissive: [0.0, 0.0, 0.0],
+            roughness: 1.0,
+            base_color_texture: None,
+            normal_texture: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct RawPart {
+    positions: Vec<f32>,
+    normals: Vec<f32>,
+    uvs: Vec<f32>,
+    /// Set once any vertex routed into this part referenced no `vn`/`vt`;
+    /// at the end we drop the whole attribute rather than ship a part with
+    /// some real and some zero-filled normals/uvs.
+    missing_normal: bool,
+    missing_uv: bool,
+}
+
+pub fn load_scene(path: &Path) -> Result<SceneData> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let text =
+        fs::read_to_string(path).with_context(|| format!("read OBJ file {}", path.display()))?;
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut texcoords: Vec<[f32; 2]> = Vec::new();
+
+    let mut material_defs: Vec<ObjMaterialDef> = vec![ObjMaterialDef::new("default".to_string())];
+    let mut material_index_by_name: HashMap<String, usize> = HashMap::new();
+    let mut current_material: usize = 0;
+
+    let mut raw_parts: HashMap<usize, RawPart> = HashMap::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let Some((keyword, rest)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let rest = rest.trim();
+
+        match keyword {
+            "v" => positions.push(parse_vec3(rest)?),
+            "vn" => normals.push(parse_vec3(rest)?),
+            "vt" => texcoords.push(parse_vec2(rest)?),
+            "mtllib" => {
+                for name in rest.split_whitespace() {
+                    let mtl_path = base_dir.join(name);
+                    let Ok(defs) = parse_mtl(&mtl_path, base_dir) else {
+                        continue;
+                    };
+                    for def in defs {
+                        material_index_by_name.insert(def.name.clone(), material_defs.len());
+                        material_defs.push(def);
+                    }
+                }
+            }
+            "usemtl" => {
+                current_material = *material_index_by_name
+                    .entry(rest.to_string())
+                    .or_insert_with(|| {
+                        material_defs.push(ObjMaterialDef::new(rest.to_string()));
+                        material_defs.len() - 1
+                    });
+            }
+            "f" => {
+                let face_verts = rest
+                    .split_whitespace()
+                    .map(|token| parse_face_vertex(token, positions.len(), texcoords.len(), normals.len()))
+                    .collect::<Result<Vec<_>>>()?;
+                if face_verts.len() < 3 {
+                    continue;
+                }
+                let part = raw_parts.entry(current_material).or_default();
+                for i in 1..face_verts.len() - 1 {
+                    for &(pos, uv, normal) in &[face_verts[0], face_verts[i], face_verts[i + 1]] {
+                        part.positions.extend_from_slice(&positions[pos]);
+                        match normal {
+                            Some(n) => part.normals.extend_from_slice(&normals[n]),
+                            None => {
+                                part.normals.extend_from_slice(&[0.0, 0.0, 0.0]);
+                                part.missing_normal = true;
+                            }
+                        }
+                        match uv {
+                            Some(t) => part.uvs.extend_from_slice(&texcoords[t]),
+                            None => {
+                                part.uvs.extend_from_slice(&[0.0, 0.0]);
+                                part.missing_uv = true;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut used_indices: Vec<usize> = raw_parts.keys().copied().collect();
+    used_indices.sort_unstable();
+
+    let mut parts = Vec::new();
+    for index in used_indices {
+        let mut raw = raw_parts.remove(&index).unwrap();
+        if raw.missing_normal {
+            raw.normals.clear();
+        }
+        if raw.missing_uv {
+            raw.uvs.clear();
+        }
+        parts.push(MeshPart {
+            name: material_defs.get(index).map(|def| def.name.clone()),
+            material_index: index,
+            positions: raw.positions,
+            normals: raw.normals,
+            uvs: raw.uvs,
+            colors: Vec::new(),
+            indices: None,
+        });
+    }
+
+    if parts.is_empty() {
+        bail!("no mesh data found in OBJ");
+    }
+
+    let materials = material_defs.iter().map(material_from_obj).collect();
+
+    Ok(SceneData {
+        materials,
+        parts,
+        // OBJ carries no axis metadata; ufbx already normalizes FBX scenes
+        // to Y-up, so default to the same convention here.
+        right_axis: AxisDir::PosX,
+        up_axis: AxisDir::PosY,
+    })
+}
+
+fn material_from_obj(def: &ObjMaterialDef) -> Material {
+    Material {
+        name: Some(def.name.clone()),
+        base_color: def.base_color,
+        emissive: def.emissive,
+        // Classic OBJ/MTL has no metalness concept; treat every material as
+        // fully dielectric.
+        metallic: 0.0,
+        roughness: def.roughness,
+        double_sided: false,
+        base_color_texture: def.base_color_texture.clone().map(TextureSource::File),
+        normal_texture: def.normal_texture.clone().map(TextureSource::File),
+        emissive_texture: None,
+        metallic_roughness_texture: None,
+        occlusion_texture: None,
+        occlusion_strength: 1.0,
+        alpha_mode: AlphaMode::Opaque,
+        alpha_cutoff: 0.5,
+        clearcoat: None,
+        transmission_factor: 0.0,
+        transmission_texture: None,
+        sheen: None,
+        ior: 1.5,
+    }
+}
+
+fn parse_mtl(path: &Path, base_dir: &Path) -> Result<Vec<ObjMaterialDef>> {
+    let text = fs::read_to_string(path).with_context(|| format!("read MTL file {}", path.display()))?;
+    let mut defs: Vec<ObjMaterialDef> = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let Some((keyword, rest)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let rest = rest.trim();
+
+        match keyword {
+            "newmtl" => defs.push(ObjMaterialDef::new(rest.to_string())),
+            "Kd" => {
+                if let Some(def) = defs.last_mut() {
+                    let rgb = parse_vec3(rest)?;
+                    def.base_color = [rgb[0], rgb[1], rgb[2], def.base_color[3]];
+                }
+            }
+            "Ke" => {
+                if let Some(def) = defs.last_mut() {
+                    def.emissive = parse_vec3(rest)?;
+                }
+            }
+            "Ns" => {
+                if let Some(def) = defs.last_mut() {
+                    let shininess: f32 = rest
+                        .split_whitespace()
+                        .next()
+                        .context("Ns missing value")?
+                        .parse()
+                        .context("invalid Ns value")?;
+                    // Approximate Blinn-Phong shininess -> glTF roughness,
+                    // matching the standard sqrt(2 / (Ns + 2)) conversion.
+                    def.roughness = (2.0 / (shininess + 2.0)).sqrt().clamp(0.0, 1.0);
+                }
+            }
+            "map_Kd" => {
+                if let Some(def) = defs.last_mut() {
+                    if let Some(filename) = rest.split_whitespace().last() {
+                        def.base_color_texture = Some(base_dir.join(filename));
+                    }
+                }
+            }
+            "map_Bump" | "bump" => {
+                if let Some(def) = defs.last_mut() {
+                    if let Some(filename) = rest.split_whitespace().last() {
+                        def.normal_texture = Some(base_dir.join(filename));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(defs)
+}
+
+fn parse_vec3(rest: &str) -> Result<[f32; 3]> {
+    let mut values = rest.split_whitespace();
+    let x = next_f32(&mut values)?;
+    let y = next_f32(&mut values)?;
+    let z = next_f32(&mut values)?;
+    Ok([x, y, z])
+}
+
+fn parse_vec2(rest: &str) -> Result<[f32; 2]> {
+    let mut values = rest.split_whitespace();
+    let u = next_f32(&mut values)?;
+    let v = next_f32(&mut values)?;
+    Ok([u, v])
+}
+
+fn next_f32<'a>(values: &mut impl Iterator<Item = &'a str>) -> Result<f32> {
+    values
+        .next()
+        .context("missing numeric component")?
+        .parse()
+        .context("invalid numeric component")
+}
+
+/// Parses one `f` face-vertex token (`v`, `v/vt`, `v//vn`, or `v/vt/vn`),
+/// resolving 1-based (and OBJ's negative relative) indices against the
+/// running vertex/texcoord/normal counts seen so far.
+fn parse_face_vertex(
+    token: &str,
+    pos_count: usize,
+    tex_count: usize,
+    norm_count: usize,
+) -> Result<(usize, Option<usize>, Option<usize>)> {
+    let mut components = token.split('/');
+    let v = components
+        .next()
+        .filter(|s| !s.is_empty())
+        .context("face vertex missing position index")?;
+    let pos = resolve_index(v, pos_count)?;
+
+    let uv = match components.next().filter(|s| !s.is_empty()) {
+        Some(s) => Some(resolve_index(s, tex_count)?),
+        None => None,
+    };
+    let normal = match components.next().filter(|s| !s.is_empty()) {
+        Some(s) => Some(resolve_index(s, norm_count)?),
+        None => None,
+    };
+
+    Ok((pos, uv, normal))
+}
+
+fn resolve_index(token: &str, count: usize) -> Result<usize> {
+    let index: i64 = token.parse().context("invalid OBJ index")?;
+    if index > 0 {
+        let resolved = (index - 1) as usize;
+        if resolved >= count {
+            bail!("OBJ index {index} out of range (only {count} defined)");
+        }
+        Ok(resolved)
+    } else if index < 0 {
+        if index.unsigned_abs() as usize > count {
+            bail!("OBJ index {index} out of range (only {count} defined)");
+        }
+        Ok((count as i64 + index) as usize)
+    } else {
+        bail!("OBJ index cannot be zero")
+    }
+}
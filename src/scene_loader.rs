@@ -0,0 +1,15 @@
+//! Dispatches `load_scene` to the right backend by input file extension, so
+//! the rest of the pipeline (tiling, GLB writing) only ever deals in
+//! `ufbx_loader::SceneData` regardless of which format produced it.
+
+use crate::obj_loader;
+use crate::ufbx_loader::{self, SceneData};
+use anyhow::Result;
+use std::path::Path;
+
+pub fn load_scene(path: &Path) -> Result<SceneData> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("obj") => obj_loader::load_scene(path),
+        _ => ufbx_loader::load_scene(path),
+    }
+}
@@ -0,0 +1,494 @@
+//! Edge-collapse mesh simplification using the quadric error metric (Garland
+//! & Heckbert), the same technique meshoptimizer uses for LOD generation.
+//! `tiles.rs` calls [`simplify`] to decimate the merged geometry of four
+//! sibling tiles into their parent's LOD level.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// 4x4 symmetric error quadric stored as its 10 distinct entries (row-major
+/// upper triangle: xx xy xz xw yy yz yw zz zw ww).
+#[derive(Clone, Copy, Debug, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Quadric([
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ])
+    }
+
+    fn add_assign(&mut self, other: &Quadric) {
+        for i in 0..10 {
+            self.0[i] += other.0[i];
+        }
+    }
+
+    fn sum(&self, other: &Quadric) -> Quadric {
+        let mut q = *self;
+        q.add_assign(other);
+        q
+    }
+
+    fn scaled(&self, factor: f64) -> Quadric {
+        let mut out = self.0;
+        for value in &mut out {
+            *value *= factor;
+        }
+        Quadric(out)
+    }
+
+    fn sym(&self) -> [[f64; 3]; 3] {
+        let [xx, xy, xz, _xw, yy, yz, _yw, zz, _zw, _ww] = self.0;
+        [[xx, xy, xz], [xy, yy, yz], [xz, yz, zz]]
+    }
+
+    fn linear(&self) -> [f64; 3] {
+        [self.0[3], self.0[6], self.0[8]]
+    }
+
+    fn error(&self, v: [f64; 3]) -> f64 {
+        let [x, y, z] = v;
+        let [xx, xy, xz, xw, yy, yz, yw, zz, zw, ww] = self.0;
+        xx * x * x
+            + 2.0 * xy * x * y
+            + 2.0 * xz * x * z
+            + 2.0 * xw * x
+            + yy * y * y
+            + 2.0 * yz * y * z
+            + 2.0 * yw * y
+            + zz * z * z
+            + 2.0 * zw * z
+            + ww
+    }
+
+    // Minimizes this quadric's error by solving the 3x3 normal-equation
+    // system `A v = -b` for its symmetric part `A` and linear term `b`.
+    // Falls back to the edge midpoint when `A` is near-singular, e.g. a
+    // quadric built from a single plane (flat along one direction).
+    fn optimal_position(&self, fallback: [f64; 3]) -> [f64; 3] {
+        let a = self.sym();
+        let det = mat3_det(&a);
+        if det.abs() < 1e-9 {
+            return fallback;
+        }
+        let b = self.linear();
+        let inv = mat3_inverse(&a, det);
+        [
+            -(inv[0][0] * b[0] + inv[0][1] * b[1] + inv[0][2] * b[2]),
+            -(inv[1][0] * b[0] + inv[1][1] * b[1] + inv[1][2] * b[2]),
+            -(inv[2][0] * b[0] + inv[2][1] * b[1] + inv[2][2] * b[2]),
+        ]
+    }
+}
+
+fn mat3_det(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+fn mat3_inverse(m: &[[f64; 3]; 3], det: f64) -> [[f64; 3]; 3] {
+    let inv_det = 1.0 / det;
+    [
+        [
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det,
+        ],
+        [
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det,
+        ],
+        [
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det,
+        ],
+    ]
+}
+
+/// Flat, per-corner triangle soup in the same layout `PartBuilder` uses:
+/// attribute `i` belongs to vertex `i`, vertices come three at a time.
+pub struct MeshSoup<'a> {
+    pub positions: &'a [f32],
+    pub normals: &'a [f32],
+    pub uvs: &'a [f32],
+    pub colors: &'a [f32],
+}
+
+pub struct SimplifiedMesh {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub uvs: Vec<f32>,
+    pub colors: Vec<f32>,
+}
+
+#[derive(Clone, Copy)]
+struct HeapEntry {
+    cost: f64,
+    v1: usize,
+    v2: usize,
+    version1: u32,
+    version2: u32,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+impl Eq for HeapEntry {}
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so a `BinaryHeap` (a max-heap) pops the cheapest edge first.
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Edge-collapse-decimates `soup` down to roughly `target_ratio` of its
+/// original triangle count. Vertices are welded by exact position first so
+/// shared edges collapse together; boundary edges get a heavy penalty
+/// quadric so open/seam edges don't erode as interior triangles disappear.
+/// Returns `soup` untouched (cloned) if it's already at or below the target.
+pub fn simplify(soup: &MeshSoup, target_ratio: f32) -> SimplifiedMesh {
+    let has_normals = soup.normals.len() == soup.positions.len();
+    let has_uvs = soup.uvs.len() * 3 == soup.positions.len() * 2;
+    let has_colors = soup.colors.len() * 3 == soup.positions.len() * 4;
+    let vertex_count = soup.positions.len() / 3;
+
+    let mut weld: HashMap<[u32; 3], usize> = HashMap::new();
+    let mut positions: Vec<[f64; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut corner_to_vertex = vec![0usize; vertex_count];
+
+    for i in 0..vertex_count {
+        let p = [
+            soup.positions[i * 3],
+            soup.positions[i * 3 + 1],
+            soup.positions[i * 3 + 2],
+        ];
+        let key = [p[0].to_bits(), p[1].to_bits(), p[2].to_bits()];
+        let vid = *weld.entry(key).or_insert_with(|| {
+            positions.push([p[0] as f64, p[1] as f64, p[2] as f64]);
+            normals.push(if has_normals {
+                [
+                    soup.normals[i * 3],
+                    soup.normals[i * 3 + 1],
+                    soup.normals[i * 3 + 2],
+                ]
+            } else {
+                [0.0; 3]
+            });
+            uvs.push(if has_uvs {
+                [soup.uvs[i * 2], soup.uvs[i * 2 + 1]]
+            } else {
+                [0.0; 2]
+            });
+            colors.push(if has_colors {
+                [
+                    soup.colors[i * 4],
+                    soup.colors[i * 4 + 1],
+                    soup.colors[i * 4 + 2],
+                    soup.colors[i * 4 + 3],
+                ]
+            } else {
+                [1.0; 4]
+            });
+            positions.len() - 1
+        });
+        corner_to_vertex[i] = vid;
+    }
+
+    let mut triangles: Vec<[usize; 3]> = corner_to_vertex
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .filter(|t| t[0] != t[1] && t[1] != t[2] && t[0] != t[2])
+        .collect();
+
+    let original_tri_count = triangles.len();
+    let target_tris = ((original_tri_count as f32) * target_ratio.clamp(0.0, 1.0)).round() as usize;
+    let target_tris = target_tris.max(1).min(original_tri_count);
+    if target_tris >= original_tri_count || positions.len() < 4 {
+        return flatten(soup, has_normals, has_uvs, has_colors);
+    }
+
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    for (ti, tri) in triangles.iter().enumerate() {
+        let plane = face_quadric(&positions, tri);
+        for &v in tri {
+            quadrics[v].add_assign(&plane);
+            vertex_triangles[v].push(ti);
+        }
+    }
+
+    // Boundary edges (used by exactly one triangle) get a stiff penalty
+    // quadric from a plane through the edge, perpendicular to its face, so
+    // the silhouette of the merged geometry doesn't retreat inward.
+    let mut edge_face_count: HashMap<(usize, usize), u32> = HashMap::new();
+    for tri in &triangles {
+        for &(a, b) in &[(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            let key = edge_key(a, b);
+            *edge_face_count.entry(key).or_insert(0) += 1;
+        }
+    }
+    const BOUNDARY_WEIGHT: f64 = 1000.0;
+    for tri in &triangles {
+        let face_normal = triangle_normal(&positions, tri);
+        for &(a, b) in &[(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            if edge_face_count[&edge_key(a, b)] != 1 {
+                continue;
+            }
+            let pa = positions[a];
+            let pb = positions[b];
+            let edge_dir = normalize(sub(pb, pa));
+            let plane_normal = normalize(cross(edge_dir, face_normal));
+            if plane_normal == [0.0; 3] {
+                continue;
+            }
+            let d = -dot(plane_normal, pa);
+            let penalty = Quadric::from_plane(plane_normal[0], plane_normal[1], plane_normal[2], d)
+                .scaled(BOUNDARY_WEIGHT);
+            quadrics[a].add_assign(&penalty);
+            quadrics[b].add_assign(&penalty);
+        }
+    }
+
+    let mut versions = vec![0u32; positions.len()];
+    let mut vertex_alive = vec![true; positions.len()];
+    let mut triangle_alive = vec![true; triangles.len()];
+
+    let mut heap: BinaryHeap<HeapEntry> = BinaryHeap::new();
+    let mut pushed_edges: std::collections::HashSet<(usize, usize)> = std::collections::HashSet::new();
+    for tri in &triangles {
+        for &(a, b) in &[(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            let key = edge_key(a, b);
+            if pushed_edges.insert(key) {
+                push_edge(&mut heap, &positions, &quadrics, &versions, key.0, key.1);
+            }
+        }
+    }
+
+    let mut live_tris = original_tri_count;
+    while live_tris > target_tris {
+        let Some(entry) = heap.pop() else { break };
+        if !vertex_alive[entry.v1]
+            || !vertex_alive[entry.v2]
+            || versions[entry.v1] != entry.version1
+            || versions[entry.v2] != entry.version2
+        {
+            continue;
+        }
+        let (v1, v2) = (entry.v1, entry.v2);
+
+        let merged_quadric = quadrics[v1].sum(&quadrics[v2]);
+        let midpoint = lerp3(positions[v1], positions[v2], 0.5);
+        let target_pos = merged_quadric.optimal_position(midpoint);
+        let t = project_onto_segment(positions[v1], positions[v2], target_pos);
+
+        positions[v1] = target_pos;
+        normals[v1] = lerp3f32(normals[v1], normals[v2], t as f32);
+        uvs[v1] = [
+            lerp_f32(uvs[v1][0], uvs[v2][0], t as f32),
+            lerp_f32(uvs[v1][1], uvs[v2][1], t as f32),
+        ];
+        colors[v1] = [
+            lerp_f32(colors[v1][0], colors[v2][0], t as f32),
+            lerp_f32(colors[v1][1], colors[v2][1], t as f32),
+            lerp_f32(colors[v1][2], colors[v2][2], t as f32),
+            lerp_f32(colors[v1][3], colors[v2][3], t as f32),
+        ];
+        quadrics[v1] = merged_quadric;
+        vertex_alive[v2] = false;
+        versions[v1] += 1;
+        versions[v2] += 1;
+
+        let v2_triangles = std::mem::take(&mut vertex_triangles[v2]);
+        for &ti in &v2_triangles {
+            if !triangle_alive[ti] {
+                continue;
+            }
+            let tri = &mut triangles[ti];
+            for slot in tri.iter_mut() {
+                if *slot == v2 {
+                    *slot = v1;
+                }
+            }
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[0] == tri[2] {
+                triangle_alive[ti] = false;
+                live_tris -= 1;
+            } else {
+                vertex_triangles[v1].push(ti);
+            }
+        }
+
+        let mut neighbors = std::collections::HashSet::new();
+        for &ti in &vertex_triangles[v1] {
+            if !triangle_alive[ti] {
+                continue;
+            }
+            for &v in &triangles[ti] {
+                if v != v1 {
+                    neighbors.insert(v);
+                }
+            }
+        }
+        for neighbor in neighbors {
+            push_edge(&mut heap, &positions, &quadrics, &versions, v1, neighbor);
+        }
+    }
+
+    let mut remap = vec![usize::MAX; positions.len()];
+    let mut out = SimplifiedMesh {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        uvs: Vec::new(),
+        colors: Vec::new(),
+    };
+    let mut next_index = 0usize;
+    for (ti, tri) in triangles.iter().enumerate() {
+        if !triangle_alive[ti] {
+            continue;
+        }
+        for &v in tri {
+            if remap[v] == usize::MAX {
+                remap[v] = next_index;
+                next_index += 1;
+            }
+            let p = positions[v];
+            out.positions.extend_from_slice(&[p[0] as f32, p[1] as f32, p[2] as f32]);
+            if has_normals {
+                out.normals.extend_from_slice(&normals[v]);
+            }
+            if has_uvs {
+                out.uvs.extend_from_slice(&uvs[v]);
+            }
+            if has_colors {
+                out.colors.extend_from_slice(&colors[v]);
+            }
+        }
+    }
+
+    out
+}
+
+fn flatten(soup: &MeshSoup, has_normals: bool, has_uvs: bool, has_colors: bool) -> SimplifiedMesh {
+    SimplifiedMesh {
+        positions: soup.positions.to_vec(),
+        normals: if has_normals { soup.normals.to_vec() } else { Vec::new() },
+        uvs: if has_uvs { soup.uvs.to_vec() } else { Vec::new() },
+        colors: if has_colors { soup.colors.to_vec() } else { Vec::new() },
+    }
+}
+
+fn push_edge(
+    heap: &mut BinaryHeap<HeapEntry>,
+    positions: &[[f64; 3]],
+    quadrics: &[Quadric],
+    versions: &[u32],
+    a: usize,
+    b: usize,
+) {
+    let merged = quadrics[a].sum(&quadrics[b]);
+    let midpoint = lerp3(positions[a], positions[b], 0.5);
+    let cost = merged.error(merged.optimal_position(midpoint));
+    heap.push(HeapEntry {
+        cost,
+        v1: a,
+        v2: b,
+        version1: versions[a],
+        version2: versions[b],
+    });
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn face_quadric(positions: &[[f64; 3]], tri: &[usize; 3]) -> Quadric {
+    let normal = triangle_normal(positions, tri);
+    let d = -dot(normal, positions[tri[0]]);
+    Quadric::from_plane(normal[0], normal[1], normal[2], d)
+}
+
+fn triangle_normal(positions: &[[f64; 3]], tri: &[usize; 3]) -> [f64; 3] {
+    let ab = sub(positions[tri[1]], positions[tri[0]]);
+    let ac = sub(positions[tri[2]], positions[tri[0]]);
+    normalize(cross(ab, ac))
+}
+
+fn project_onto_segment(a: [f64; 3], b: [f64; 3], p: [f64; 3]) -> f64 {
+    let ab = sub(b, a);
+    let len_sq = dot(ab, ab);
+    if len_sq < 1e-18 {
+        return 0.5;
+    }
+    (dot(sub(p, a), ab) / len_sq).clamp(0.0, 1.0)
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f64; 3]) -> [f64; 3] {
+    let len = dot(v, v).sqrt();
+    if len < 1e-12 {
+        [0.0; 3]
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+fn lerp3(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+    ]
+}
+
+fn lerp3f32(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        lerp_f32(a[0], b[0], t),
+        lerp_f32(a[1], b[1], t),
+        lerp_f32(a[2], b[2], t),
+    ]
+}
+
+fn lerp_f32(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
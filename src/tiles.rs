@@ -1,12 +1,57 @@
-use crate::geo::GeoContext;
-use crate::gltf_writer::{write_glb_with_textures, TextureCache, TextureMode};
-use crate::ufbx_loader::{AxisDir, Material, MeshPart, SceneData};
+use crate::bvh;
+use crate::geo::{
+    bounding_sphere_local, mercator_zoom_for_tile_size, slippy_tile_index, Crs, Ellipsoid,
+    GeoContext,
+};
+use crate::gltf_writer::{
+    write_compressed_file, write_glb_with_compression, MeshCompression, TextureCache,
+    TextureMode, TileCompression,
+};
+use crate::image_utils::TextureOptions;
+use crate::simplify;
+use crate::ufbx_loader::{weld_scene, AxisDir, Material, MeshPart, SceneData};
 use anyhow::{bail, Context, Result};
+use h3o::{LatLng, Resolution};
 use serde_json::json;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+/// Tiling scheme for `export_tileset`. `Quadtree` (the historical default)
+/// buckets triangles on a 2D X/Z grid with each tile spanning the full
+/// vertical extent of the scene; `Octree` additionally splits on Y so tall
+/// or vertically dense scenes get real 3D hierarchical culling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Subdivision {
+    #[default]
+    Quadtree,
+    Octree,
+}
+
+/// Color space `Vertex::color` is authored in, used by `interpolate_vertex`
+/// to blend correctly across a clipped edge. glTF vertex colors are sRGB,
+/// so a naive linear lerp of the raw channel values visibly darkens clip
+/// seams; `Srgb` round-trips each RGB channel through linear space before
+/// lerping to avoid that. Alpha is always lerped linearly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+/// How `weld_vertices` resolves `normal`/`uv`/`color` when several source
+/// vertices merge into one welded vertex. `pos_local`/`pos_enu`/`tangent`
+/// always come from whichever vertex first claimed the grid cell, since
+/// they're already within `epsilon` of each other by construction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeldMerge {
+    #[default]
+    Average,
+    KeepFirst,
+}
+
 pub struct TilesetOptions {
     pub origin_lat: f64,
     pub origin_lon: f64,
@@ -17,6 +62,66 @@ pub struct TilesetOptions {
     pub min_tile_size: f64,
     pub max_level: Option<u32>,
     pub embed_textures: bool,
+    pub crs: Crs,
+    /// H3 resolution (0-15) to tag each content tile's center with, for
+    /// downstream spatial joins/clustering without reparsing geometry. `None`
+    /// skips H3 tagging entirely.
+    pub h3_resolution: Option<u8>,
+    pub subdivision: Subdivision,
+    /// Extrude a thin downward skirt along each tile boundary edge so a
+    /// high-detail leaf doesn't show a crack against a coarser decimated
+    /// neighbor. Off by default since it adds geometry to every tile.
+    pub generate_skirts: bool,
+    /// Skirt depth as a fraction of the tile's own size. Ignored unless
+    /// `generate_skirts` is set.
+    pub skirt_depth_ratio: f64,
+    /// After clipping, replace any vertex normal that collapsed to
+    /// near-zero (or flipped relative to the triangle's winding) with the
+    /// triangle's geometric face normal. Off by default since it overrides
+    /// authored shading on every affected triangle, not just the genuinely
+    /// broken ones.
+    pub recompute_collapsed_normals: bool,
+    /// Run `validate_and_repair_part` on every tile's parts before writing
+    /// them out: zeroes non-finite positions/normals/uvs, renormalizes salvageable
+    /// normals, and drops degenerate triangles. Off by default since a
+    /// well-formed FBX never triggers it and it's an extra pass over every
+    /// tile's geometry.
+    pub validate_and_repair_mesh: bool,
+    /// Weld vertices whose positions round to the same `weld_epsilon` grid
+    /// cell (in ENU space)
+    /// after clipping and skirt generation, closing T-junction cracks where
+    /// independently-clipped edges landed a hair apart. Off by default
+    /// since it's an extra pass over every tile's geometry.
+    pub weld_tile_seams: bool,
+    /// Grid size for `weld_tile_seams`, in meters. Vertices whose positions
+    /// round to the same cell of this grid are merged into one. Ignored
+    /// unless `weld_tile_seams` is set.
+    pub weld_epsilon: f64,
+    /// How `weld_tile_seams` picks attributes for a merged vertex.
+    pub weld_merge: WeldMerge,
+    pub mesh_compression: MeshCompression,
+    /// Gzip-compresses every written tile GLB and `tileset.json` in place
+    /// (see `TileCompression`). Off by default since it costs CPU on every
+    /// tile and requires the serving host to send `Content-Encoding: gzip`
+    /// for these paths to benefit from it.
+    pub tile_compression: TileCompression,
+    /// flate2 compression level (0-9) used when `tile_compression` is
+    /// `Gzip`. Higher trades CPU time for smaller output.
+    pub compression_level: u32,
+    pub texture_options: TextureOptions,
+    /// Max `MeshPart`s per leaf for `export_bvh_tileset`'s part-level BVH.
+    /// Ignored by the grid/quadtree `export_tileset` path. `None` defaults
+    /// to 8.
+    pub bvh_max_parts_per_leaf: Option<usize>,
+    /// Weld coincident vertices and emit an indexed mesh for each written
+    /// tile, instead of the flat triangle soup the tiling/clipping pipeline
+    /// builds internally. Off by default, like the gltf-mode `--weld-vertices`
+    /// flag it mirrors.
+    pub weld_vertices: bool,
+    /// Position quantization epsilon (in scene units) used by
+    /// `weld_vertices`; vertices whose positions round to the same cell of
+    /// this grid merge.
+    pub weld_vertices_epsilon: f32,
 }
 
 #[derive(Clone)]
@@ -39,6 +144,7 @@ struct TileBucket {
 struct TileNode {
     level: u32,
     x: i32,
+    y: i32,
     z: i32,
     min_local: [f64; 3],
     max_local: [f64; 3],
@@ -53,6 +159,9 @@ struct Vertex {
     normal: [f32; 3],
     uv: [f32; 2],
     color: [f32; 4],
+    /// xyz direction + w handedness sign, shared by all three vertices of
+    /// the source triangle this `Vertex` came from.
+    tangent: [f32; 4],
 }
 
 // ufbx 已统一输出为 Y-up，这里无需额外轴变换。
@@ -74,6 +183,7 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
         options.origin_height,
         options.heading,
         options.scale,
+        Ellipsoid::WGS84,
     );
 
     let max_level = options
@@ -81,7 +191,7 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
         .unwrap_or_else(|| compute_max_level(options.tile_size, options.min_tile_size));
     let leaf_size = options.tile_size / 2_f64.powi(max_level as i32);
 
-    let mut buckets: HashMap<(i32, i32), TileBucket> = HashMap::new();
+    let mut buckets: HashMap<(i32, i32, i32), TileBucket> = HashMap::new();
     let mut global_min_local = [f64::INFINITY; 3];
     let mut global_max_local = [f64::NEG_INFINITY; 3];
 
@@ -180,12 +290,17 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
                 ([0.0; 4], [0.0; 4], [0.0; 4])
             };
 
+            // Shared by all three vertices: handedness is constant per
+            // triangle, so there's no per-vertex variant to compute here.
+            let tri_tangent = face_tangent(p0, p1, p2, uv0, uv1, uv2, n0);
+
             let v0 = Vertex {
                 pos_local: p0,
                 pos_enu: w0,
                 normal: n0,
                 uv: uv0,
                 color: c0,
+                tangent: tri_tangent,
             };
             let v1 = Vertex {
                 pos_local: p1,
@@ -193,6 +308,7 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
                 normal: n1,
                 uv: uv1,
                 color: c1,
+                tangent: tri_tangent,
             };
             let v2 = Vertex {
                 pos_local: p2,
@@ -200,6 +316,7 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
                 normal: n2,
                 uv: uv2,
                 color: c2,
+                tangent: tri_tangent,
             };
             let tri_vertices = [v0, v1, v2];
 
@@ -213,117 +330,144 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
             let tile_z_min = (tri_min_z / leaf_size).floor() as i32;
             let tile_z_max = (tri_max_z / leaf_size).floor() as i32;
 
+            // In Quadtree mode every tile spans the full vertical extent, so
+            // there is a single Y bucket with no clip plane on that axis. In
+            // Octree mode Y subdivides just like X/Z, into true cube cells.
+            let (tile_y_min, tile_y_max) = if options.subdivision == Subdivision::Octree {
+                let tri_min_y = w0[1].min(w1[1]).min(w2[1]);
+                let tri_max_y = w0[1].max(w1[1]).max(w2[1]);
+                (
+                    (tri_min_y / leaf_size).floor() as i32,
+                    (tri_max_y / leaf_size).floor() as i32,
+                )
+            } else {
+                (0, 0)
+            };
+
             for tile_x in tile_x_min..=tile_x_max {
                 let x0 = tile_x as f64 * leaf_size;
                 let x1 = x0 + leaf_size;
                 for tile_z in tile_z_min..=tile_z_max {
                     let z0 = tile_z as f64 * leaf_size;
                     let z1 = z0 + leaf_size;
-
-                    let polygon =
-                        clip_triangle_to_tile(&tri_vertices, x0, x1, z0, z1, has_normals);
-                    if polygon.len() < 3 {
-                        continue;
-                    }
-
-                    let bucket = buckets
-                        .entry((tile_x, tile_z))
-                        .or_insert_with(|| TileBucket {
-                            parts: HashMap::new(),
-                            min_local: [f64::INFINITY; 3],
-                            max_local: [f64::NEG_INFINITY; 3],
-                        });
-
-                    let first = &polygon[0];
-                    for i in 1..polygon.len() - 1 {
-                        let a = first;
-                        let b = &polygon[i];
-                        let c = &polygon[i + 1];
-                        if is_degenerate_triangle(a, b, c) {
+                    for tile_y in tile_y_min..=tile_y_max {
+                        let (y0, y1) = if options.subdivision == Subdivision::Octree {
+                            let y0 = tile_y as f64 * leaf_size;
+                            (y0, y0 + leaf_size)
+                        } else {
+                            (f64::NEG_INFINITY, f64::INFINITY)
+                        };
+
+                        let clipped = clip_triangle_to_tile(
+                            &tri_vertices,
+                            x0,
+                            x1,
+                            y0,
+                            y1,
+                            z0,
+                            z1,
+                            has_normals,
+                            options.recompute_collapsed_normals,
+                        );
+                        if clipped.is_empty() {
                             continue;
                         }
 
-                        let mut tri_min_local = [f64::INFINITY; 3];
-                        let mut tri_max_local = [f64::NEG_INFINITY; 3];
-                        for vertex in [a, b, c] {
-                            let local = vertex.pos_local;
-                            tri_min_local[0] = tri_min_local[0].min(local[0]);
-                            tri_min_local[1] = tri_min_local[1].min(local[1]);
-                            tri_min_local[2] = tri_min_local[2].min(local[2]);
-                            tri_max_local[0] = tri_max_local[0].max(local[0]);
-                            tri_max_local[1] = tri_max_local[1].max(local[1]);
-                            tri_max_local[2] = tri_max_local[2].max(local[2]);
-                        }
-
-                        for axis in 0..3 {
-                            bucket.min_local[axis] =
-                                bucket.min_local[axis].min(tri_min_local[axis]);
-                            bucket.max_local[axis] =
-                                bucket.max_local[axis].max(tri_max_local[axis]);
-                            global_min_local[axis] =
-                                global_min_local[axis].min(tri_min_local[axis]);
-                            global_max_local[axis] =
-                                global_max_local[axis].max(tri_max_local[axis]);
-                        }
-
-                        let builder =
-                            bucket
-                                .parts
-                                .entry(part.material_index)
-                                .or_insert_with(|| PartBuilder {
-                                    name: part.name.clone(),
-                                    material_index: part.material_index,
-                                    positions: Vec::new(),
-                                    normals: Vec::new(),
-                                    uvs: Vec::new(),
-                                    colors: Vec::new(),
-                                });
-
-                        builder.positions.extend_from_slice(&[
-                            a.pos_local[0] as f32,
-                            a.pos_local[1] as f32,
-                            a.pos_local[2] as f32,
-                            b.pos_local[0] as f32,
-                            b.pos_local[1] as f32,
-                            b.pos_local[2] as f32,
-                            c.pos_local[0] as f32,
-                            c.pos_local[1] as f32,
-                            c.pos_local[2] as f32,
-                        ]);
-
-                        if has_normals {
-                            builder.normals.extend_from_slice(&[
-                                a.normal[0],
-                                a.normal[1],
-                                a.normal[2],
-                                b.normal[0],
-                                b.normal[1],
-                                b.normal[2],
-                                c.normal[0],
-                                c.normal[1],
-                                c.normal[2],
-                            ]);
-                        }
-                        if has_uvs {
-                            builder.uvs.extend_from_slice(&[
-                                a.uv[0], a.uv[1], b.uv[0], b.uv[1], c.uv[0], c.uv[1],
-                            ]);
-                        }
-                        if has_colors {
-                            builder.colors.extend_from_slice(&[
-                                a.color[0],
-                                a.color[1],
-                                a.color[2],
-                                a.color[3],
-                                b.color[0],
-                                b.color[1],
-                                b.color[2],
-                                b.color[3],
-                                c.color[0],
-                                c.color[1],
-                                c.color[2],
-                                c.color[3],
+                        let bucket = buckets
+                            .entry((tile_x, tile_y, tile_z))
+                            .or_insert_with(|| TileBucket {
+                                parts: HashMap::new(),
+                                min_local: [f64::INFINITY; 3],
+                                max_local: [f64::NEG_INFINITY; 3],
+                            });
+
+                        for out_tri in clipped.chunks_exact(3) {
+                            let a = &out_tri[0];
+                            let b = &out_tri[1];
+                            let c = &out_tri[2];
+
+                            let mut tri_min_local = [f64::INFINITY; 3];
+                            let mut tri_max_local = [f64::NEG_INFINITY; 3];
+                            for vertex in [a, b, c] {
+                                let local = vertex.pos_local;
+                                tri_min_local[0] = tri_min_local[0].min(local[0]);
+                                tri_min_local[1] = tri_min_local[1].min(local[1]);
+                                tri_min_local[2] = tri_min_local[2].min(local[2]);
+                                tri_max_local[0] = tri_max_local[0].max(local[0]);
+                                tri_max_local[1] = tri_max_local[1].max(local[1]);
+                                tri_max_local[2] = tri_max_local[2].max(local[2]);
+                            }
+
+                            for axis in 0..3 {
+                                bucket.min_local[axis] =
+                                    bucket.min_local[axis].min(tri_min_local[axis]);
+                                bucket.max_local[axis] =
+                                    bucket.max_local[axis].max(tri_max_local[axis]);
+                                global_min_local[axis] =
+                                    global_min_local[axis].min(tri_min_local[axis]);
+                                global_max_local[axis] =
+                                    global_max_local[axis].max(tri_max_local[axis]);
+                            }
+
+                            let builder =
+                                bucket
+                                    .parts
+                                    .entry(part.material_index)
+                                    .or_insert_with(|| PartBuilder {
+                                        name: part.name.clone(),
+                                        material_index: part.material_index,
+                                        positions: Vec::new(),
+                                        normals: Vec::new(),
+                                        uvs: Vec::new(),
+                                        colors: Vec::new(),
+                                    });
+
+                            builder.positions.extend_from_slice(&[
+                                a.pos_local[0] as f32,
+                                a.pos_local[1] as f32,
+                                a.pos_local[2] as f32,
+                                b.pos_local[0] as f32,
+                                b.pos_local[1] as f32,
+                                b.pos_local[2] as f32,
+                                c.pos_local[0] as f32,
+                                c.pos_local[1] as f32,
+                                c.pos_local[2] as f32,
                             ]);
+
+                            if has_normals {
+                                builder.normals.extend_from_slice(&[
+                                    a.normal[0],
+                                    a.normal[1],
+                                    a.normal[2],
+                                    b.normal[0],
+                                    b.normal[1],
+                                    b.normal[2],
+                                    c.normal[0],
+                                    c.normal[1],
+                                    c.normal[2],
+                                ]);
+                            }
+                            if has_uvs {
+                                builder.uvs.extend_from_slice(&[
+                                    a.uv[0], a.uv[1], b.uv[0], b.uv[1], c.uv[0], c.uv[1],
+                                ]);
+                            }
+                            if has_colors {
+                                builder.colors.extend_from_slice(&[
+                                    a.color[0],
+                                    a.color[1],
+                                    a.color[2],
+                                    a.color[3],
+                                    b.color[0],
+                                    b.color[1],
+                                    b.color[2],
+                                    b.color[3],
+                                    c.color[0],
+                                    c.color[1],
+                                    c.color[2],
+                                    c.color[3],
+                                ]);
+                            }
                         }
                     }
                 }
@@ -335,8 +479,6 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
         bail!("no triangles were assigned to tiles");
     }
 
-    let (min_tile_x, max_tile_x, min_tile_z, max_tile_z) = tile_index_bounds(&buckets);
-
     let tiles_dir = output_dir.join("tiles");
     fs::create_dir_all(&tiles_dir)
         .with_context(|| format!("create tiles dir {}", tiles_dir.display()))?;
@@ -350,59 +492,214 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
         Some(TextureCache::new(textures_dir, "../textures"))
     };
 
-    for ((x, z), bucket) in &buckets {
-        let scene_tile = build_tile_scene(bucket, &scene.materials, scene.right_axis, scene.up_axis);
-        let filename = tile_filename(max_level, *x, *z);
-        let path = tiles_dir.join(filename);
-        if let Some(cache) = texture_cache.as_mut() {
-            let mut mode = TextureMode::External(cache);
-            write_glb_with_textures(&scene_tile, &path, &mut mode)
-                .with_context(|| format!("write tile {}", path.display()))?;
-        } else {
-            let mut mode = TextureMode::Embed;
-            write_glb_with_textures(&scene_tile, &path, &mut mode)
-                .with_context(|| format!("write tile {}", path.display()))?;
-        }
-    }
-
-    let root_transform = geo.transform_matrix();
-    let root_error = options.tile_size * 0.5;
-    let force_refine_error = root_error * 1_000_000.0;
-    let heading_rad = options.heading.to_radians();
-    let scale = options.scale;
     // 本地坐标按 Y 为上轴。
     let up_axis = 1usize;
-    let root_box = rotate_box_y_up_to_z_up(grid_extent_box(
-        min_tile_x,
-        max_tile_x,
-        min_tile_z,
-        max_tile_z,
-        leaf_size,
-        global_min_local[up_axis],
-        global_max_local[up_axis],
-        heading_rad,
-        scale,
-    ));
-
-    let mut root_children: Vec<TileNode> = buckets
-        .into_iter()
-        .map(|((x, z), bucket)| {
-            let mut min_local = bucket.min_local;
-            let mut max_local = bucket.max_local;
+
+    // Leaf level: write each bucket's full-resolution geometry as-is, and
+    // seed the bottom of the LOD pyramid with its node + geometry.
+    let mut level_geometry: HashMap<(i32, i32, i32), HashMap<usize, PartBuilder>> = HashMap::new();
+    let mut level_nodes: HashMap<(i32, i32, i32), TileNode> = HashMap::new();
+    for ((x, y, z), mut bucket) in buckets {
+        if options.generate_skirts {
+            let (y0, y1) = if options.subdivision == Subdivision::Octree {
+                (y as f64 * leaf_size, (y as f64 + 1.0) * leaf_size)
+            } else {
+                (f64::NEG_INFINITY, f64::INFINITY)
+            };
+            let bounds = [
+                x as f64 * leaf_size,
+                (x as f64 + 1.0) * leaf_size,
+                y0,
+                y1,
+                z as f64 * leaf_size,
+                (z as f64 + 1.0) * leaf_size,
+            ];
+            add_tile_skirts(
+                &mut bucket.parts,
+                &geo,
+                bounds,
+                up_axis,
+                leaf_size * options.skirt_depth_ratio,
+            );
+        }
+
+        if options.weld_tile_seams {
+            for part in bucket.parts.values_mut() {
+                weld_part(part, &geo, options.weld_epsilon, options.weld_merge);
+            }
+        }
+
+        let filename = tile_filename(max_level, x, y, z);
+        if options.validate_and_repair_mesh {
+            for part in bucket.parts.values_mut() {
+                let report = validate_and_repair_part(part);
+                if !report.is_clean() {
+                    eprintln!("warning: tile {filename} mesh repair: {report:?}");
+                }
+            }
+        }
+        let path = tiles_dir.join(filename);
+        write_tile_glb(
+            &bucket.parts,
+            &scene.materials,
+            scene.right_axis,
+            scene.up_axis,
+            &path,
+            texture_cache.as_mut(),
+            &options.texture_options,
+            options.mesh_compression,
+            options.tile_compression,
+            options.compression_level,
+            options.weld_vertices,
+            options.weld_vertices_epsilon,
+        )?;
+
+        let mut min_local = bucket.min_local;
+        let mut max_local = bucket.max_local;
+        if options.subdivision == Subdivision::Quadtree {
             min_local[up_axis] = global_min_local[up_axis];
             max_local[up_axis] = global_max_local[up_axis];
+        }
+        level_nodes.insert(
+            (x, y, z),
             TileNode {
                 level: max_level,
                 x,
+                y,
                 z,
                 min_local,
                 max_local,
                 has_content: true,
                 children: Vec::new(),
+            },
+        );
+        level_geometry.insert((x, y, z), bucket.parts);
+    }
+
+    // Parent levels: merge each group of up-to-eight (four in Quadtree mode)
+    // sibling tiles and decimate the union down to `LOD_DECIMATE_RATIO` of
+    // its triangle count, so a viewer gets real LOD instead of "every leaf
+    // or nothing".
+    for level in (0..max_level).rev() {
+        let mut parent_children: HashMap<(i32, i32, i32), Vec<TileNode>> = HashMap::new();
+        let mut parent_geometry_parts: HashMap<(i32, i32, i32), Vec<&HashMap<usize, PartBuilder>>> =
+            HashMap::new();
+
+        for (&(x, y, z), node) in &level_nodes {
+            let parent_key = (x >> 1, y >> 1, z >> 1);
+            parent_children
+                .entry(parent_key)
+                .or_default()
+                .push(node.clone());
+            parent_geometry_parts
+                .entry(parent_key)
+                .or_default()
+                .push(&level_geometry[&(x, y, z)]);
+        }
+
+        let mut next_geometry = HashMap::new();
+        let mut next_nodes = HashMap::new();
+        for (parent_key, children) in parent_children {
+            let merged = merge_parts(&parent_geometry_parts[&parent_key]);
+            let mut decimated = decimate_parts(&merged, LOD_DECIMATE_RATIO);
+
+            if options.generate_skirts {
+                let tile_size_at_level = leaf_size * 2_f64.powi((max_level - level) as i32);
+                let (y0, y1) = if options.subdivision == Subdivision::Octree {
+                    (
+                        parent_key.1 as f64 * tile_size_at_level,
+                        (parent_key.1 as f64 + 1.0) * tile_size_at_level,
+                    )
+                } else {
+                    (f64::NEG_INFINITY, f64::INFINITY)
+                };
+                let bounds = [
+                    parent_key.0 as f64 * tile_size_at_level,
+                    (parent_key.0 as f64 + 1.0) * tile_size_at_level,
+                    y0,
+                    y1,
+                    parent_key.2 as f64 * tile_size_at_level,
+                    (parent_key.2 as f64 + 1.0) * tile_size_at_level,
+                ];
+                add_tile_skirts(
+                    &mut decimated,
+                    &geo,
+                    bounds,
+                    up_axis,
+                    tile_size_at_level * options.skirt_depth_ratio,
+                );
             }
-        })
-        .collect();
-    root_children.sort_by_key(|node| (node.z, node.x));
+
+            if options.weld_tile_seams {
+                for part in decimated.values_mut() {
+                    weld_part(part, &geo, options.weld_epsilon, options.weld_merge);
+                }
+            }
+
+            let filename = tile_filename(level, parent_key.0, parent_key.1, parent_key.2);
+            if options.validate_and_repair_mesh {
+                for part in decimated.values_mut() {
+                    let report = validate_and_repair_part(part);
+                    if !report.is_clean() {
+                        eprintln!("warning: tile {filename} mesh repair: {report:?}");
+                    }
+                }
+            }
+            let path = tiles_dir.join(filename);
+            write_tile_glb(
+                &decimated,
+                &scene.materials,
+                scene.right_axis,
+                scene.up_axis,
+                &path,
+                texture_cache.as_mut(),
+                &options.texture_options,
+                options.mesh_compression,
+                options.tile_compression,
+                options.compression_level,
+                options.weld_vertices,
+                options.weld_vertices_epsilon,
+            )?;
+
+            let mut min_local = children[0].min_local;
+            let mut max_local = children[0].max_local;
+            for child in &children[1..] {
+                for axis in 0..3 {
+                    min_local[axis] = min_local[axis].min(child.min_local[axis]);
+                    max_local[axis] = max_local[axis].max(child.max_local[axis]);
+                }
+            }
+
+            next_nodes.insert(
+                parent_key,
+                TileNode {
+                    level,
+                    x: parent_key.0,
+                    y: parent_key.1,
+                    z: parent_key.2,
+                    min_local,
+                    max_local,
+                    has_content: true,
+                    children,
+                },
+            );
+            next_geometry.insert(parent_key, decimated);
+        }
+
+        level_nodes = next_nodes;
+        level_geometry = next_geometry;
+    }
+
+    let root_transform = geo.transform_matrix();
+    let root_error = options.tile_size * 0.5;
+    let force_refine_error = root_error * 1_000_000.0;
+    let root_pad = options.tile_size * 0.01;
+    let (root_min, root_max) = pad_local_bounds(global_min_local, global_max_local, root_pad);
+    let root_region = region_from_local_bounds(&geo, root_min, root_max);
+    let mercator_zoom = mercator_zoom_for_tile_size(leaf_size);
+
+    let mut root_children: Vec<TileNode> = level_nodes.into_values().collect();
+    root_children.sort_by_key(|node| (node.z, node.y, node.x));
 
     let tileset = json!({
         "asset": {
@@ -412,19 +709,21 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
         "geometricError": force_refine_error,
         "root": {
             "transform": root_transform,
-            "boundingVolume": { "box": root_box },
+            "boundingVolume": { "region": root_region },
             "geometricError": force_refine_error,
             "refine": "REPLACE",
             "children": root_children
                 .into_iter()
                 .map(|node| {
                     tile_node_to_json(
+                        &geo,
                         node,
                         options.tile_size,
-                        heading_rad,
-                        scale,
                         root_error,
                         force_refine_error,
+                        options.crs,
+                        mercator_zoom,
+                        options.h3_resolution,
                     )
                 })
                 .collect::<Vec<_>>()
@@ -432,9 +731,13 @@ pub fn export_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOpt
     });
 
     let tileset_path = output_dir.join("tileset.json");
-    let file = fs::File::create(&tileset_path)
-        .with_context(|| format!("write tileset {}", tileset_path.display()))?;
-    serde_json::to_writer_pretty(file, &tileset)?;
+    let tileset_bytes = serde_json::to_vec_pretty(&tileset)?;
+    write_compressed_file(
+        &tileset_path,
+        &tileset_bytes,
+        options.tile_compression,
+        options.compression_level,
+    )?;
 
     Ok(())
 }
@@ -449,17 +752,20 @@ fn compute_max_level(tile_size: f64, min_tile_size: f64) -> u32 {
     level
 }
 
-fn tile_filename(level: u32, x: i32, z: i32) -> String {
-    format!("L{level}_X{x}_Z{z}.glb")
+fn tile_filename(level: u32, x: i32, y: i32, z: i32) -> String {
+    format!("L{level}_X{x}_Y{y}_Z{z}.glb")
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_tile_scene(
-    bucket: &TileBucket,
+    parts: &HashMap<usize, PartBuilder>,
     materials: &[Material],
     right_axis: AxisDir,
     up_axis: AxisDir,
+    weld_vertices: bool,
+    weld_vertices_epsilon: f32,
 ) -> SceneData {
-    let mut used_indices: Vec<usize> = bucket.parts.keys().copied().collect();
+    let mut used_indices: Vec<usize> = parts.keys().copied().collect();
     used_indices.sort_unstable();
 
     let mut remap = HashMap::new();
@@ -471,7 +777,7 @@ fn build_tile_scene(
     }
 
     let mut tile_parts = Vec::new();
-    for builder in bucket.parts.values() {
+    for builder in parts.values() {
         let mapped_index = remap.get(&builder.material_index).copied().unwrap_or(0);
         tile_parts.push(MeshPart {
             name: builder.name.clone(),
@@ -480,157 +786,206 @@ fn build_tile_scene(
             normals: builder.normals.clone(),
             uvs: builder.uvs.clone(),
             colors: builder.colors.clone(),
+            indices: None,
         });
     }
 
-    SceneData {
+    let mut scene_tile = SceneData {
         materials: tile_materials,
         parts: tile_parts,
         right_axis,
         up_axis,
+    };
+    if weld_vertices {
+        weld_scene(&mut scene_tile, weld_vertices_epsilon);
     }
+    scene_tile
 }
 
-fn tile_index_bounds(buckets: &HashMap<(i32, i32), TileBucket>) -> (i32, i32, i32, i32) {
-    let mut min_x = i32::MAX;
-    let mut max_x = i32::MIN;
-    let mut min_z = i32::MAX;
-    let mut max_z = i32::MIN;
-    for (x, z) in buckets.keys() {
-        min_x = min_x.min(*x);
-        max_x = max_x.max(*x);
-        min_z = min_z.min(*z);
-        max_z = max_z.max(*z);
+// Fraction of triangles a parent LOD level keeps relative to the union of
+// its children, matching the request's "e.g. 50%" starting point.
+const LOD_DECIMATE_RATIO: f32 = 0.5;
+
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments)]
+fn write_tile_glb(
+    parts: &HashMap<usize, PartBuilder>,
+    materials: &[Material],
+    right_axis: AxisDir,
+    up_axis: AxisDir,
+    path: &Path,
+    texture_cache: Option<&mut TextureCache>,
+    texture_options: &TextureOptions,
+    mesh_compression: MeshCompression,
+    tile_compression: TileCompression,
+    compression_level: u32,
+    weld_vertices: bool,
+    weld_vertices_epsilon: f32,
+) -> Result<()> {
+    let scene_tile = build_tile_scene(
+        parts,
+        materials,
+        right_axis,
+        up_axis,
+        weld_vertices,
+        weld_vertices_epsilon,
+    );
+    match texture_cache {
+        Some(cache) => {
+            let mut mode = TextureMode::External(cache);
+            write_glb_with_compression(
+                &scene_tile,
+                path,
+                &mut mode,
+                texture_options,
+                mesh_compression,
+                tile_compression,
+                compression_level,
+            )
+        }
+        None => {
+            let mut mode = TextureMode::Embed;
+            write_glb_with_compression(
+                &scene_tile,
+                path,
+                &mut mode,
+                texture_options,
+                mesh_compression,
+                tile_compression,
+                compression_level,
+            )
+        }
     }
-    (min_x, max_x, min_z, max_z)
+    .with_context(|| format!("write tile {}", path.display()))
 }
 
-fn bounds_to_box(min: [f64; 3], max: [f64; 3]) -> [f64; 12] {
-    let center = [
-        0.5 * (min[0] + max[0]),
-        0.5 * (min[1] + max[1]),
-        0.5 * (min[2] + max[2]),
-    ];
-    let half = [
-        0.5 * (max[0] - min[0]),
-        0.5 * (max[1] - min[1]),
-        0.5 * (max[2] - min[2]),
-    ];
-    [
-        center[0],
-        center[1],
-        center[2],
-        half[0],
-        0.0,
-        0.0,
-        0.0,
-        half[1],
-        0.0,
-        0.0,
-        0.0,
-        half[2],
-    ]
+fn merge_parts(maps: &[&HashMap<usize, PartBuilder>]) -> HashMap<usize, PartBuilder> {
+    let mut merged: HashMap<usize, PartBuilder> = HashMap::new();
+    for map in maps {
+        for (material_index, builder) in map.iter() {
+            let entry = merged
+                .entry(*material_index)
+                .or_insert_with(|| PartBuilder {
+                    name: builder.name.clone(),
+                    material_index: *material_index,
+                    positions: Vec::new(),
+                    normals: Vec::new(),
+                    uvs: Vec::new(),
+                    colors: Vec::new(),
+                });
+            entry.positions.extend_from_slice(&builder.positions);
+            entry.normals.extend_from_slice(&builder.normals);
+            entry.uvs.extend_from_slice(&builder.uvs);
+            entry.colors.extend_from_slice(&builder.colors);
+        }
+    }
+    merged
+}
+
+fn decimate_parts(parts: &HashMap<usize, PartBuilder>, ratio: f32) -> HashMap<usize, PartBuilder> {
+    parts
+        .iter()
+        .map(|(material_index, builder)| {
+            let soup = simplify::MeshSoup {
+                positions: &builder.positions,
+                normals: &builder.normals,
+                uvs: &builder.uvs,
+                colors: &builder.colors,
+            };
+            let simplified = simplify::simplify(&soup, ratio);
+            (
+                *material_index,
+                PartBuilder {
+                    name: builder.name.clone(),
+                    material_index: *material_index,
+                    positions: simplified.positions,
+                    normals: simplified.normals,
+                    uvs: simplified.uvs,
+                    colors: simplified.colors,
+                },
+            )
+        })
+        .collect()
 }
 
-// Cesium 3D Tiles 以 Z-up 为默认约定，输出包围盒需从 Y-up 旋转到 Z-up。
-fn rotate_box_y_up_to_z_up(box_bounds: [f64; 12]) -> [f64; 12] {
-    fn rotate(v: [f64; 3]) -> [f64; 3] {
-        [v[0], -v[2], v[1]]
+fn pad_local_bounds(min: [f64; 3], max: [f64; 3], pad: f64) -> ([f64; 3], [f64; 3]) {
+    let mut min = min;
+    let mut max = max;
+    for i in 0..3 {
+        min[i] -= pad;
+        max[i] += pad;
     }
+    (min, max)
+}
 
-    let center = rotate([box_bounds[0], box_bounds[1], box_bounds[2]]);
-    let axis_x = rotate([box_bounds[3], box_bounds[4], box_bounds[5]]);
-    let axis_y = rotate([box_bounds[6], box_bounds[7], box_bounds[8]]);
-    let axis_z = rotate([box_bounds[9], box_bounds[10], box_bounds[11]]);
-    [
-        center[0],
-        center[1],
-        center[2],
-        axis_x[0],
-        axis_x[1],
-        axis_x[2],
-        axis_y[0],
-        axis_y[1],
-        axis_y[2],
-        axis_z[0],
-        axis_z[1],
-        axis_z[2],
-    ]
+// H3 indices print conventionally as lowercase hex (e.g. "8928308280fffff");
+// we keep that convention here so the extras round-trip through any
+// off-the-shelf H3 tool without a base conversion.
+fn h3_cell_extras(lat_deg: f64, lon_deg: f64, resolution: u8) -> Option<serde_json::Value> {
+    let resolution = Resolution::try_from(resolution).ok()?;
+    let cell = LatLng::new(lat_deg, lon_deg).ok()?.to_cell(resolution);
+
+    let parents: Vec<String> = (0..resolution.into())
+        .rev()
+        .filter_map(|r| Resolution::try_from(r).ok())
+        .filter_map(|r| cell.parent(r))
+        .map(|parent| format!("{:x}", u64::from(parent)))
+        .collect();
+
+    let mut h3 = serde_json::Map::new();
+    h3.insert("cell".to_string(), json!(format!("{:x}", u64::from(cell))));
+    if !parents.is_empty() {
+        h3.insert("parents".to_string(), json!(parents));
+    }
+    Some(serde_json::Value::Object(h3))
 }
 
-fn grid_extent_box(
-    min_tile_x: i32,
-    max_tile_x: i32,
-    min_tile_z: i32,
-    max_tile_z: i32,
-    leaf_size: f64,
-    min_y: f64,
-    max_y: f64,
-    heading_rad: f64,
-    scale: f64,
-) -> [f64; 12] {
-    let pad_ratio = 0.005;
-    let pad_enu = leaf_size * pad_ratio;
-
-    let min_x_enu = (min_tile_x as f64) * leaf_size;
-    let max_x_enu = ((max_tile_x + 1) as f64) * leaf_size;
-    let min_z_enu = (min_tile_z as f64) * leaf_size;
-    let max_z_enu = ((max_tile_z + 1) as f64) * leaf_size;
-
-    let center_enu_x = 0.5 * (min_x_enu + max_x_enu);
-    let center_enu_z = 0.5 * (min_z_enu + max_z_enu);
-    let half_x = 0.5 * (max_x_enu - min_x_enu) + pad_enu;
-    let half_z = 0.5 * (max_z_enu - min_z_enu) + pad_enu;
-
-    let (sin_h, cos_h) = heading_rad.sin_cos();
-    let inv_scale = if scale.abs() < 1e-12 { 0.0 } else { 1.0 / scale };
-    let inv_scale_abs = inv_scale.abs();
-
-    let center_x = (center_enu_x * cos_h + center_enu_z * sin_h) * inv_scale;
-    let center_z = (-center_enu_x * sin_h + center_enu_z * cos_h) * inv_scale;
-
-    let mut min_y = min_y;
-    let mut max_y = max_y;
-    if max_y < min_y {
-        std::mem::swap(&mut min_y, &mut max_y);
-    }
-    let mut pad_y = (max_y - min_y) * 0.02;
-    let pad_local = pad_enu * inv_scale_abs;
-    if pad_y < pad_local {
-        pad_y = pad_local;
-    }
-    min_y -= pad_y;
-    max_y += pad_y;
-    let center_y = 0.5 * (min_y + max_y);
-    let half_y = 0.5 * (max_y - min_y);
-
-    let axis_x = [half_x * cos_h * inv_scale, 0.0, -half_x * sin_h * inv_scale];
-    let axis_y = [0.0, half_y, 0.0];
-    let axis_z = [half_z * sin_h * inv_scale, 0.0, half_z * cos_h * inv_scale];
+// Runs all eight corners of a local-space AABB through the geo context into
+// ECEF, then Bowring-inverts each corner back to geodetic and takes the
+// min/max per axis. This is the `region` analogue of an ECEF bounding box:
+// a true lat/lon/height footprint instead of an oriented box in ECEF.
+fn region_from_local_bounds(geo: &GeoContext, min: [f64; 3], max: [f64; 3]) -> [f64; 6] {
+    let corners = [
+        [min[0], min[1], min[2]],
+        [max[0], min[1], min[2]],
+        [min[0], max[1], min[2]],
+        [max[0], max[1], min[2]],
+        [min[0], min[1], max[2]],
+        [max[0], min[1], max[2]],
+        [min[0], max[1], max[2]],
+        [max[0], max[1], max[2]],
+    ];
 
-    [
-        center_x,
-        center_y,
-        center_z,
-        axis_x[0],
-        axis_x[1],
-        axis_x[2],
-        axis_y[0],
-        axis_y[1],
-        axis_y[2],
-        axis_z[0],
-        axis_z[1],
-        axis_z[2],
-    ]
+    let mut west = f64::INFINITY;
+    let mut east = f64::NEG_INFINITY;
+    let mut south = f64::INFINITY;
+    let mut north = f64::NEG_INFINITY;
+    let mut min_height = f64::INFINITY;
+    let mut max_height = f64::NEG_INFINITY;
+
+    for corner in corners {
+        let ecef = geo.local_to_ecef(corner);
+        let (lat, lon, height) = geo.ecef_to_geodetic(ecef);
+        west = west.min(lon);
+        east = east.max(lon);
+        south = south.min(lat);
+        north = north.max(lat);
+        min_height = min_height.min(height);
+        max_height = max_height.max(height);
+    }
+
+    [west, south, east, north, min_height, max_height]
 }
 
 fn tile_node_to_json(
+    geo: &GeoContext,
     node: TileNode,
     tile_size: f64,
-    _heading_rad: f64,
-    _scale: f64,
     base_error: f64,
     force_refine_error: f64,
+    crs: Crs,
+    mercator_zoom: u32,
+    h3_resolution: Option<u8>,
 ) -> serde_json::Value {
     let geometric_error = if node.children.is_empty() {
         0.0
@@ -640,27 +995,62 @@ fn tile_node_to_json(
         force_refine_error
     };
 
-    // Use actual geometry bounds instead of grid cell bounds
-    // Add small padding to avoid zero volume
-    let mut min = node.min_local;
-    let mut max = node.max_local;
+    // Use actual geometry bounds instead of grid cell bounds, with a small
+    // padding so a flat tile doesn't collapse to a zero-volume region.
     let pad = tile_size * 0.01;
-    for i in 0..3 {
-        min[i] -= pad;
-        max[i] += pad;
-    }
+    let (min, max) = pad_local_bounds(node.min_local, node.max_local, pad);
+
+    // Content tiles get a tight bounding sphere around their own geometry,
+    // in the same local frame as the GLB content itself: the inherited
+    // tileset `transform` places it, so it must not be pre-transformed to
+    // ECEF here or a conformant client would apply that transform twice.
+    // Non-content (refinement-only) nodes keep the geographic `region`
+    // footprint, since they have no geometry of their own to center on.
+    let bounding_volume = if node.has_content {
+        json!({ "sphere": bounding_sphere_local(min, max) })
+    } else {
+        json!({ "region": region_from_local_bounds(geo, min, max) })
+    };
 
-    let box_bounds = rotate_box_y_up_to_z_up(bounds_to_box(min, max));
     let mut json_node = json!({
-        "boundingVolume": { "box": box_bounds },
+        "boundingVolume": bounding_volume,
         "geometricError": geometric_error,
         "refine": "REPLACE"
     });
 
     if node.has_content {
         json_node["content"] = json!({
-            "uri": format!("tiles/{}", tile_filename(node.level, node.x, node.z))
+            "uri": format!("tiles/{}", tile_filename(node.level, node.x, node.y, node.z))
         });
+
+        if crs == Crs::WebMercator || h3_resolution.is_some() {
+            let center = [
+                0.5 * (min[0] + max[0]),
+                0.5 * (min[1] + max[1]),
+                0.5 * (min[2] + max[2]),
+            ];
+            let (lat, lon, _height) = geo.ecef_to_geodetic(geo.local_to_ecef(center));
+            let lat_deg = lat.to_degrees();
+            let lon_deg = lon.to_degrees();
+
+            let mut extras = serde_json::Map::new();
+            if crs == Crs::WebMercator {
+                let (tile_x, tile_y) = slippy_tile_index(lon_deg, lat_deg, mercator_zoom);
+                extras.insert("crs".to_string(), json!("EPSG:3857"));
+                extras.insert(
+                    "slippy".to_string(),
+                    json!({ "z": mercator_zoom, "x": tile_x, "y": tile_y }),
+                );
+            }
+            if let Some(resolution) = h3_resolution {
+                if let Some(h3_json) = h3_cell_extras(lat_deg, lon_deg, resolution) {
+                    extras.insert("h3".to_string(), h3_json);
+                }
+            }
+            if !extras.is_empty() {
+                json_node["extras"] = serde_json::Value::Object(extras);
+            }
+        }
     }
 
     if !node.children.is_empty() {
@@ -669,12 +1059,14 @@ fn tile_node_to_json(
                 .into_iter()
                 .map(|child| {
                     tile_node_to_json(
+                        geo,
                         child,
                         tile_size,
-                        _heading_rad,
-                        _scale,
                         base_error,
                         force_refine_error,
+                        crs,
+                        mercator_zoom,
+                        h3_resolution,
                     )
                 })
                 .collect(),
@@ -684,36 +1076,143 @@ fn tile_node_to_json(
     json_node
 }
 
+/// Clips a triangle against a tile's AABB and fan-triangulates the result,
+/// so callers get back a flat list of output triangles (a multiple of 3
+/// vertices) rather than a convex polygon to triangulate themselves.
+/// Infinite Y bounds in Quadtree mode make the Y-axis clips no-ops, so
+/// Octree's extra planes cost nothing when a tile isn't Y-subdivided.
+#[allow(clippy::too_many_arguments)]
 fn clip_triangle_to_tile(
     vertices: &[Vertex; 3],
     x0: f64,
     x1: f64,
+    y0: f64,
+    y1: f64,
     z0: f64,
     z1: f64,
     normalize_normals: bool,
+    recompute_collapsed_normals: bool,
+) -> Vec<Vertex> {
+    clip_triangle_against_aabb(
+        vertices,
+        [x0, y0, z0],
+        [x1, y1, z1],
+        normalize_normals,
+        ColorSpace::Srgb,
+        recompute_collapsed_normals,
+    )
+}
+
+/// Clips a triangle against a full axis-aligned bounding box via
+/// Sutherland-Hodgman polygon clipping against all six planes, then
+/// fan-triangulates the surviving polygon and drops any degenerate output
+/// triangle. When `recompute_collapsed_normals` is set, any output vertex
+/// whose interpolated normal collapsed to near-zero or flipped relative to
+/// the triangle's winding is repaired with the triangle's face normal.
+#[allow(clippy::too_many_arguments)]
+fn clip_triangle_against_aabb(
+    tri: &[Vertex; 3],
+    min: [f64; 3],
+    max: [f64; 3],
+    normalize_normals: bool,
+    color_space: ColorSpace,
+    recompute_collapsed_normals: bool,
 ) -> Vec<Vertex> {
-    let mut poly = vec![vertices[0].clone(), vertices[1].clone(), vertices[2].clone()];
-    poly = clip_polygon(&poly, 0, x0, true, normalize_normals);
-    if poly.is_empty() {
-        return poly;
+    let planes = [
+        (0usize, min[0], true),
+        (0usize, max[0], false),
+        (1usize, min[1], true),
+        (1usize, max[1], false),
+        (2usize, min[2], true),
+        (2usize, max[2], false),
+    ];
+
+    let mut polygon = vec![tri[0].clone(), tri[1].clone(), tri[2].clone()];
+    for &(axis, bound, keep_greater) in &planes {
+        if polygon.is_empty() {
+            break;
+        }
+        polygon = clip_polygon_against_plane(
+            &polygon,
+            axis,
+            bound,
+            keep_greater,
+            normalize_normals,
+            color_space,
+        );
+    }
+
+    let mut out = Vec::new();
+    if polygon.len() < 3 {
+        return out;
+    }
+    for i in 1..polygon.len() - 1 {
+        let (a, b, c) = (&polygon[0], &polygon[i], &polygon[i + 1]);
+        if is_degenerate_triangle(a, b, c) {
+            continue;
+        }
+        let mut a = a.clone();
+        let mut b = b.clone();
+        let mut c = c.clone();
+        if recompute_collapsed_normals {
+            repair_collapsed_normals(&mut a, &mut b, &mut c);
+        }
+        out.push(a);
+        out.push(b);
+        out.push(c);
     }
-    poly = clip_polygon(&poly, 0, x1, false, normalize_normals);
-    if poly.is_empty() {
-        return poly;
+    out
+}
+
+/// Replaces any of `a`/`b`/`c`'s normal with the triangle's geometric face
+/// normal (derived from local-space positions, the same space `Vertex::normal`
+/// is authored in) when that vertex's normal is near-zero or points away
+/// from the face it belongs to. Opposed source normals interpolating across
+/// a clip edge can land exactly on zero, and `normalize3` leaves a zero
+/// vector as-is, so left unrepaired these vertices break shading.
+fn repair_collapsed_normals(a: &mut Vertex, b: &mut Vertex, c: &mut Vertex) {
+    let face_normal = face_normal_local(a, b, c);
+    if face_normal == [0.0, 0.0, 0.0] {
+        return;
     }
-    poly = clip_polygon(&poly, 2, z0, true, normalize_normals);
-    if poly.is_empty() {
-        return poly;
+    for v in [a, b, c] {
+        let len_sq = v.normal[0] * v.normal[0] + v.normal[1] * v.normal[1] + v.normal[2] * v.normal[2];
+        let dot = v.normal[0] * face_normal[0] + v.normal[1] * face_normal[1] + v.normal[2] * face_normal[2];
+        if len_sq < 1e-8 || dot < 0.0 {
+            v.normal = face_normal;
+        }
     }
-    clip_polygon(&poly, 2, z1, false, normalize_normals)
 }
 
-fn clip_polygon(
+/// Geometric face normal from `pos_local` edges, normalized. Returns the
+/// zero vector for a degenerate triangle (callers already filter those out
+/// via `is_degenerate_triangle`, but this stays safe to call independently).
+fn face_normal_local(a: &Vertex, b: &Vertex, c: &Vertex) -> [f32; 3] {
+    let ab = [
+        (b.pos_local[0] - a.pos_local[0]) as f32,
+        (b.pos_local[1] - a.pos_local[1]) as f32,
+        (b.pos_local[2] - a.pos_local[2]) as f32,
+    ];
+    let ac = [
+        (c.pos_local[0] - a.pos_local[0]) as f32,
+        (c.pos_local[1] - a.pos_local[1]) as f32,
+        (c.pos_local[2] - a.pos_local[2]) as f32,
+    ];
+    normalize3([
+        ab[1] * ac[2] - ab[2] * ac[1],
+        ab[2] * ac[0] - ab[0] * ac[2],
+        ab[0] * ac[1] - ab[1] * ac[0],
+    ])
+}
+
+#[allow(clippy::too_many_arguments)]
+fn clip_polygon_against_plane(
     vertices: &[Vertex],
     axis: usize,
     value: f64,
     keep_greater: bool,
     normalize_normals: bool,
+    color_space: ColorSpace,
 ) -> Vec<Vertex> {
     if vertices.is_empty() {
         return Vec::new();
@@ -723,13 +1222,18 @@ fn clip_polygon(
     let mut prev_inside = inside_plane(prev, axis, value, keep_greater);
     for curr in vertices {
         let curr_inside = inside_plane(curr, axis, value, keep_greater);
+        if curr_inside != prev_inside {
+            output.push(clip_vertex_against_plane(
+                prev,
+                curr,
+                axis,
+                value,
+                normalize_normals,
+                color_space,
+            ));
+        }
         if curr_inside {
-            if !prev_inside {
-                output.push(intersect_plane(prev, curr, axis, value, normalize_normals));
-            }
             output.push(curr.clone());
-        } else if prev_inside {
-            output.push(intersect_plane(prev, curr, axis, value, normalize_normals));
         }
         prev = curr;
         prev_inside = curr_inside;
@@ -737,37 +1241,42 @@ fn clip_polygon(
     output
 }
 
-fn inside_plane(vertex: &Vertex, axis: usize, value: f64, keep_greater: bool) -> bool {
-    let eps = 1e-9;
-    if keep_greater {
-        vertex.pos_enu[axis] >= value - eps
-    } else {
-        vertex.pos_enu[axis] <= value + eps
-    }
-}
-
-fn intersect_plane(
+/// Computes the edge/plane crossing vertex without clamping `t`: callers
+/// derive `value` from the same AABB they're clipping against, so a
+/// crossing in `[0, 1]` is already guaranteed.
+fn clip_vertex_against_plane(
     a: &Vertex,
     b: &Vertex,
     axis: usize,
     value: f64,
     normalize_normals: bool,
+    color_space: ColorSpace,
 ) -> Vertex {
     let denom = b.pos_enu[axis] - a.pos_enu[axis];
-    let mut t = if denom.abs() < 1e-12 {
+    let t = if denom.abs() < 1e-12 {
         0.0
     } else {
         (value - a.pos_enu[axis]) / denom
     };
-    if t < 0.0 {
-        t = 0.0;
-    } else if t > 1.0 {
-        t = 1.0;
+    interpolate_vertex(a, b, t, normalize_normals, color_space)
+}
+
+fn inside_plane(vertex: &Vertex, axis: usize, value: f64, keep_greater: bool) -> bool {
+    let eps = 1e-9;
+    if keep_greater {
+        vertex.pos_enu[axis] >= value - eps
+    } else {
+        vertex.pos_enu[axis] <= value + eps
     }
-    interpolate_vertex(a, b, t, normalize_normals)
 }
 
-fn interpolate_vertex(a: &Vertex, b: &Vertex, t: f64, normalize_normals: bool) -> Vertex {
+fn interpolate_vertex(
+    a: &Vertex,
+    b: &Vertex,
+    t: f64,
+    normalize_normals: bool,
+    color_space: ColorSpace,
+) -> Vertex {
     let tf = t as f32;
     let mut normal = [
         lerp_f32(a.normal[0], b.normal[0], tf),
@@ -777,6 +1286,14 @@ fn interpolate_vertex(a: &Vertex, b: &Vertex, t: f64, normalize_normals: bool) -
     if normalize_normals {
         normal = normalize3(normal);
     }
+    let mut tangent = [
+        lerp_f32(a.tangent[0], b.tangent[0], tf),
+        lerp_f32(a.tangent[1], b.tangent[1], tf),
+        lerp_f32(a.tangent[2], b.tangent[2], tf),
+    ];
+    if normalize_normals {
+        tangent = normalize3(tangent);
+    }
     Vertex {
         pos_local: [
             lerp_f64(a.pos_local[0], b.pos_local[0], t),
@@ -793,12 +1310,46 @@ fn interpolate_vertex(a: &Vertex, b: &Vertex, t: f64, normalize_normals: bool) -
             lerp_f32(a.uv[0], b.uv[0], tf),
             lerp_f32(a.uv[1], b.uv[1], tf),
         ],
-        color: [
-            lerp_f32(a.color[0], b.color[0], tf),
-            lerp_f32(a.color[1], b.color[1], tf),
-            lerp_f32(a.color[2], b.color[2], tf),
-            lerp_f32(a.color[3], b.color[3], tf),
+        color: lerp_color(a.color, b.color, tf, color_space),
+        tangent: [tangent[0], tangent[1], tangent[2], a.tangent[3]],
+    }
+}
+
+/// Lerps a vertex color. `Srgb` round-trips RGB through linear space first,
+/// since straight lerp of sRGB-encoded channels visibly darkens clip seams;
+/// alpha is always lerped directly.
+fn lerp_color(a: [f32; 4], b: [f32; 4], t: f32, color_space: ColorSpace) -> [f32; 4] {
+    match color_space {
+        ColorSpace::Linear => [
+            lerp_f32(a[0], b[0], t),
+            lerp_f32(a[1], b[1], t),
+            lerp_f32(a[2], b[2], t),
+            lerp_f32(a[3], b[3], t),
         ],
+        ColorSpace::Srgb => {
+            let mut rgb = [0.0f32; 3];
+            for i in 0..3 {
+                let linear = lerp_f32(srgb_to_linear(a[i]), srgb_to_linear(b[i]), t);
+                rgb[i] = linear_to_srgb(linear);
+            }
+            [rgb[0], rgb[1], rgb[2], lerp_f32(a[3], b[3], t)]
+        }
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
     }
 }
 
@@ -819,6 +1370,70 @@ fn normalize3(v: [f32; 3]) -> [f32; 3] {
     [v[0] * inv_len, v[1] * inv_len, v[2] * inv_len]
 }
 
+/// Computes a source triangle's tangent (xyz direction + w handedness
+/// sign) from its edges and UV deltas, the same construction
+/// `gltf_writer::compute_tangents` uses for the final merged mesh. `Vertex`
+/// carries this through clipping so a clip seam's interpolated vertices
+/// keep a usable tangent without forcing a downstream recompute.
+fn face_tangent(
+    p0: [f64; 3],
+    p1: [f64; 3],
+    p2: [f64; 3],
+    uv0: [f32; 2],
+    uv1: [f32; 2],
+    uv2: [f32; 2],
+    normal: [f32; 3],
+) -> [f32; 4] {
+    let edge1 = [
+        (p1[0] - p0[0]) as f32,
+        (p1[1] - p0[1]) as f32,
+        (p1[2] - p0[2]) as f32,
+    ];
+    let edge2 = [
+        (p2[0] - p0[0]) as f32,
+        (p2[1] - p0[1]) as f32,
+        (p2[2] - p0[2]) as f32,
+    ];
+    let delta_uv1 = [uv1[0] - uv0[0], uv1[1] - uv0[1]];
+    let delta_uv2 = [uv2[0] - uv0[0], uv2[1] - uv0[1]];
+
+    let denom = delta_uv1[0] * delta_uv2[1] - delta_uv1[1] * delta_uv2[0];
+    let (tangent, bitangent) = if denom.abs() > f32::EPSILON {
+        let r = 1.0 / denom;
+        let tangent = [
+            (edge1[0] * delta_uv2[1] - edge2[0] * delta_uv1[1]) * r,
+            (edge1[1] * delta_uv2[1] - edge2[1] * delta_uv1[1]) * r,
+            (edge1[2] * delta_uv2[1] - edge2[2] * delta_uv1[1]) * r,
+        ];
+        let bitangent = [
+            (edge2[0] * delta_uv1[0] - edge1[0] * delta_uv2[0]) * r,
+            (edge2[1] * delta_uv1[0] - edge1[1] * delta_uv2[0]) * r,
+            (edge2[2] * delta_uv1[0] - edge1[2] * delta_uv2[0]) * r,
+        ];
+        (tangent, bitangent)
+    } else {
+        ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0])
+    };
+
+    let dot = normal[0] * tangent[0] + normal[1] * tangent[1] + normal[2] * tangent[2];
+    let mut t = [
+        tangent[0] - normal[0] * dot,
+        tangent[1] - normal[1] * dot,
+        tangent[2] - normal[2] * dot,
+    ];
+    t = normalize3(t);
+
+    let cross = [
+        normal[1] * t[2] - normal[2] * t[1],
+        normal[2] * t[0] - normal[0] * t[2],
+        normal[0] * t[1] - normal[1] * t[0],
+    ];
+    let handedness_dot = cross[0] * bitangent[0] + cross[1] * bitangent[1] + cross[2] * bitangent[2];
+    let w = if handedness_dot < 0.0 { -1.0 } else { 1.0 };
+
+    [t[0], t[1], t[2], w]
+}
+
 fn is_degenerate_triangle(a: &Vertex, b: &Vertex, c: &Vertex) -> bool {
     let ab = [
         b.pos_enu[0] - a.pos_enu[0],
@@ -838,3 +1453,682 @@ fn is_degenerate_triangle(a: &Vertex, b: &Vertex, c: &Vertex) -> bool {
     let area_sq = cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2];
     area_sq < 1e-20
 }
+
+/// Extrudes a thin downward skirt quad strip along each edge of `parts` that
+/// lies on one of the tile's own boundary planes, so a high-detail tile
+/// doesn't show a crack against a coarser decimated neighbor. Must run after
+/// the final clip/decimate pass for this tile: it detects boundary edges by
+/// re-deriving each vertex's world (ENU) position and comparing it against
+/// `bounds`, so stale bounds would miss edges or skirt interior ones.
+fn add_tile_skirts(
+    parts: &mut HashMap<usize, PartBuilder>,
+    geo: &GeoContext,
+    bounds: [f64; 6],
+    up_axis: usize,
+    skirt_depth: f64,
+) {
+    let [x0, x1, y0, y1, z0, z1] = bounds;
+    let mut planes = vec![(0usize, x0), (0, x1), (2usize, z0), (2, z1)];
+    if y0.is_finite() {
+        planes.push((1usize, y0));
+    }
+    if y1.is_finite() {
+        planes.push((1usize, y1));
+    }
+
+    for builder in parts.values_mut() {
+        let tri_count = builder.positions.len() / 9;
+        let has_normals = builder.normals.len() == builder.positions.len();
+        let has_uvs = builder.uvs.len() * 3 == builder.positions.len() * 2;
+        let has_colors = builder.colors.len() * 3 == builder.positions.len() * 4;
+
+        let mut extra_positions = Vec::new();
+        let mut extra_normals = Vec::new();
+        let mut extra_uvs = Vec::new();
+        let mut extra_colors = Vec::new();
+
+        for tri in 0..tri_count {
+            let base = tri * 9;
+            let corners = [base, base + 3, base + 6];
+            let local: Vec<[f64; 3]> = corners
+                .iter()
+                .map(|&c| {
+                    [
+                        builder.positions[c] as f64,
+                        builder.positions[c + 1] as f64,
+                        builder.positions[c + 2] as f64,
+                    ]
+                })
+                .collect();
+            let enu: Vec<[f64; 3]> = local.iter().map(|&p| geo.transform_local(p)).collect();
+
+            for edge in 0..3 {
+                let i0 = edge;
+                let i1 = (edge + 1) % 3;
+                let on_shared_plane = planes
+                    .iter()
+                    .any(|&(axis, value)| on_plane(enu[i0][axis], value) && on_plane(enu[i1][axis], value));
+                if !on_shared_plane {
+                    continue;
+                }
+
+                let vi0 = corners[i0];
+                let vi1 = corners[i1];
+                // `skirt_depth` is an ENU-space distance (it's compared against
+                // `bounds`, which is ENU too), but `local` is pre-transform
+                // local space, so divide back out the scale `transform_local`
+                // applies before subtracting it from the local up coordinate.
+                let local_skirt_depth = skirt_depth / geo.scale();
+                let mut lowered0 = local[i0];
+                lowered0[up_axis] -= local_skirt_depth;
+                let mut lowered1 = local[i1];
+                lowered1[up_axis] -= local_skirt_depth;
+
+                extra_positions.extend_from_slice(&[
+                    builder.positions[vi0],
+                    builder.positions[vi0 + 1],
+                    builder.positions[vi0 + 2],
+                    builder.positions[vi1],
+                    builder.positions[vi1 + 1],
+                    builder.positions[vi1 + 2],
+                    lowered0[0] as f32,
+                    lowered0[1] as f32,
+                    lowered0[2] as f32,
+                    builder.positions[vi1],
+                    builder.positions[vi1 + 1],
+                    builder.positions[vi1 + 2],
+                    lowered1[0] as f32,
+                    lowered1[1] as f32,
+                    lowered1[2] as f32,
+                    lowered0[0] as f32,
+                    lowered0[1] as f32,
+                    lowered0[2] as f32,
+                ]);
+
+                if has_normals {
+                    let n0 = [
+                        builder.normals[vi0],
+                        builder.normals[vi0 + 1],
+                        builder.normals[vi0 + 2],
+                    ];
+                    let n1 = [
+                        builder.normals[vi1],
+                        builder.normals[vi1 + 1],
+                        builder.normals[vi1 + 2],
+                    ];
+                    extra_normals.extend_from_slice(&[
+                        n0[0], n0[1], n0[2], n1[0], n1[1], n1[2], n0[0], n0[1], n0[2], n1[0],
+                        n1[1], n1[2], n1[0], n1[1], n1[2], n0[0], n0[1], n0[2],
+                    ]);
+                }
+                if has_uvs {
+                    let uv_base0 = (vi0 / 3) * 2;
+                    let uv_base1 = (vi1 / 3) * 2;
+                    let uv0 = [builder.uvs[uv_base0], builder.uvs[uv_base0 + 1]];
+                    let uv1 = [builder.uvs[uv_base1], builder.uvs[uv_base1 + 1]];
+                    extra_uvs.extend_from_slice(&[
+                        uv0[0], uv0[1], uv1[0], uv1[1], uv0[0], uv0[1], uv1[0], uv1[1], uv1[0],
+                        uv1[1], uv0[0], uv0[1],
+                    ]);
+                }
+                if has_colors {
+                    let c_base0 = (vi0 / 3) * 4;
+                    let c_base1 = (vi1 / 3) * 4;
+                    let c0 = [
+                        builder.colors[c_base0],
+                        builder.colors[c_base0 + 1],
+                        builder.colors[c_base0 + 2],
+                        builder.colors[c_base0 + 3],
+                    ];
+                    let c1 = [
+                        builder.colors[c_base1],
+                        builder.colors[c_base1 + 1],
+                        builder.colors[c_base1 + 2],
+                        builder.colors[c_base1 + 3],
+                    ];
+                    extra_colors.extend_from_slice(&[
+                        c0[0], c0[1], c0[2], c0[3], c1[0], c1[1], c1[2], c1[3], c0[0], c0[1],
+                        c0[2], c0[3], c1[0], c1[1], c1[2], c1[3], c1[0], c1[1], c1[2], c1[3],
+                        c0[0], c0[1], c0[2], c0[3],
+                    ]);
+                }
+            }
+        }
+
+        builder.positions.extend(extra_positions);
+        if has_normals {
+            builder.normals.extend(extra_normals);
+        }
+        if has_uvs {
+            builder.uvs.extend(extra_uvs);
+        }
+        if has_colors {
+            builder.colors.extend(extra_colors);
+        }
+    }
+}
+
+fn on_plane(value: f64, target: f64) -> bool {
+    (value - target).abs() < 1e-6
+}
+
+/// Defect counts from `validate_mesh`/`repair_mesh`. A report with every
+/// field zero means the mesh was already clean.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshReport {
+    pub non_finite_positions: usize,
+    pub non_finite_normals: usize,
+    pub non_finite_uvs: usize,
+    pub out_of_range_indices: usize,
+    pub zero_length_normals: usize,
+    pub degenerate_triangles: usize,
+}
+
+impl MeshReport {
+    pub fn is_clean(&self) -> bool {
+        *self == MeshReport::default()
+    }
+}
+
+/// Scans an indexed triangle mesh (3 floats/vertex positions, optionally
+/// matching normals, optionally 2 floats/vertex uvs) for the defects
+/// `repair_mesh` knows how to fix, without mutating anything: non-finite
+/// position/normal/uv components, indices past the end of `positions`,
+/// zero-length normals, and degenerate triangles.
+pub fn validate_mesh(positions: &[f32], normals: &[f32], uvs: &[f32], indices: &[u32]) -> MeshReport {
+    let vertex_count = positions.len() / 3;
+    let has_normals = normals.len() == positions.len();
+    let has_uvs = uvs.len() * 3 == positions.len() * 2;
+
+    let mut report = MeshReport::default();
+    for p in positions.chunks_exact(3) {
+        if p.iter().any(|v| !v.is_finite()) {
+            report.non_finite_positions += 1;
+        }
+    }
+    if has_normals {
+        for n in normals.chunks_exact(3) {
+            if n.iter().any(|v| !v.is_finite()) {
+                report.non_finite_normals += 1;
+            } else if n[0] * n[0] + n[1] * n[1] + n[2] * n[2] <= 0.0 {
+                report.zero_length_normals += 1;
+            }
+        }
+    }
+    if has_uvs {
+        for uv in uvs.chunks_exact(2) {
+            if uv.iter().any(|v| !v.is_finite()) {
+                report.non_finite_uvs += 1;
+            }
+        }
+    }
+    for tri in indices.chunks_exact(3) {
+        if tri.iter().any(|&i| i as usize >= vertex_count) {
+            report.out_of_range_indices += 1;
+            continue;
+        }
+        if is_degenerate_indexed_triangle(positions, tri[0] as usize, tri[1] as usize, tri[2] as usize) {
+            report.degenerate_triangles += 1;
+        }
+    }
+    report
+}
+
+/// Repairs `positions`/`normals`/`uvs` in place and rewrites `indices` to
+/// drop out-of-range and degenerate triangles, returning the report of what
+/// it found. Non-finite position/uv components are zeroed; a normal with any
+/// non-finite component is zeroed first, then every normal is renormalized
+/// (a normal that's still zero-length after that has no salvageable
+/// direction and is left as-is — there's no face to fall back to from just
+/// an index buffer; callers that want a face-normal fallback should use
+/// `repair_collapsed_normals` instead, which has the triangle's vertices).
+pub fn repair_mesh(
+    positions: &mut [f32],
+    normals: &mut [f32],
+    uvs: &mut [f32],
+    indices: &mut Vec<u32>,
+) -> MeshReport {
+    let report = validate_mesh(positions, normals, uvs, indices);
+    let vertex_count = positions.len() / 3;
+    let has_normals = normals.len() == positions.len();
+    let has_uvs = uvs.len() * 3 == positions.len() * 2;
+
+    for v in positions.iter_mut() {
+        if !v.is_finite() {
+            *v = 0.0;
+        }
+    }
+    if has_uvs {
+        for v in uvs.iter_mut() {
+            if !v.is_finite() {
+                *v = 0.0;
+            }
+        }
+    }
+    if has_normals {
+        for n in normals.chunks_exact_mut(3) {
+            for v in n.iter_mut() {
+                if !v.is_finite() {
+                    *v = 0.0;
+                }
+            }
+            let fixed = normalize3([n[0], n[1], n[2]]);
+            n.copy_from_slice(&fixed);
+        }
+    }
+
+    let kept: Vec<u32> = indices
+        .chunks_exact(3)
+        .filter(|tri| {
+            tri.iter().all(|&i| (i as usize) < vertex_count)
+                && !is_degenerate_indexed_triangle(positions, tri[0] as usize, tri[1] as usize, tri[2] as usize)
+        })
+        .flatten()
+        .copied()
+        .collect();
+    *indices = kept;
+
+    report
+}
+
+fn is_degenerate_indexed_triangle(positions: &[f32], i0: usize, i1: usize, i2: usize) -> bool {
+    let p = |i: usize| [positions[i * 3], positions[i * 3 + 1], positions[i * 3 + 2]];
+    let (a, b, c) = (p(i0), p(i1), p(i2));
+    let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        ab[1] * ac[2] - ab[2] * ac[1],
+        ab[2] * ac[0] - ab[0] * ac[2],
+        ab[0] * ac[1] - ab[1] * ac[0],
+    ];
+    cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2] < 1e-20
+}
+
+/// Adapts `repair_mesh` to a `PartBuilder`'s flat non-indexed triangle soup
+/// (every 3 vertices form one triangle): builds an identity index per
+/// vertex, repairs positions/normals/uvs in place, then gathers every array
+/// — including `colors`, which `repair_mesh` doesn't know about — through
+/// the repaired index list so a dropped degenerate triangle actually
+/// disappears instead of just being counted.
+fn validate_and_repair_part(part: &mut PartBuilder) -> MeshReport {
+    let vertex_count = part.positions.len() / 3;
+    if vertex_count == 0 {
+        return MeshReport::default();
+    }
+    let mut indices: Vec<u32> = (0..vertex_count as u32).collect();
+    let report = repair_mesh(&mut part.positions, &mut part.normals, &mut part.uvs, &mut indices);
+
+    if indices.len() == vertex_count {
+        return report;
+    }
+
+    let has_normals = part.normals.len() == part.positions.len();
+    let has_uvs = part.uvs.len() * 3 == part.positions.len() * 2;
+    let has_colors = part.colors.len() * 3 == part.positions.len() * 4;
+
+    let mut positions = Vec::with_capacity(indices.len() * 3);
+    let mut normals = Vec::with_capacity(if has_normals { indices.len() * 3 } else { 0 });
+    let mut uvs = Vec::with_capacity(if has_uvs { indices.len() * 2 } else { 0 });
+    let mut colors = Vec::with_capacity(if has_colors { indices.len() * 4 } else { 0 });
+    for &i in &indices {
+        let i = i as usize;
+        positions.extend_from_slice(&part.positions[i * 3..i * 3 + 3]);
+        if has_normals {
+            normals.extend_from_slice(&part.normals[i * 3..i * 3 + 3]);
+        }
+        if has_uvs {
+            uvs.extend_from_slice(&part.uvs[i * 2..i * 2 + 2]);
+        }
+        if has_colors {
+            colors.extend_from_slice(&part.colors[i * 4..i * 4 + 4]);
+        }
+    }
+    part.positions = positions;
+    part.normals = normals;
+    part.uvs = uvs;
+    part.colors = colors;
+
+    report
+}
+
+/// Welds coincident vertices in an indexed `Vertex` buffer: quantizes each
+/// vertex's `pos_enu` onto an `epsilon`-sized grid and merges every vertex
+/// landing in the same cell, so two triangles produced by independent clip
+/// passes that left near-identical boundary vertices share one vertex
+/// instead of a hairline T-junction crack. `indices` is rewritten against
+/// the deduplicated vertex list, and any triangle that degenerates (two or
+/// three corners welding together) is dropped.
+pub fn weld_vertices(
+    verts: &[Vertex],
+    indices: &[u32],
+    epsilon: f64,
+    merge: WeldMerge,
+) -> (Vec<Vertex>, Vec<u32>) {
+    if epsilon <= 0.0 || verts.is_empty() {
+        return (verts.to_vec(), indices.to_vec());
+    }
+
+    let mut cells: HashMap<[i64; 3], u32> = HashMap::new();
+    let mut welded: Vec<Vertex> = Vec::new();
+    let mut counts: Vec<u32> = Vec::new();
+    let mut remap: Vec<u32> = Vec::with_capacity(verts.len());
+
+    for v in verts {
+        let key = [
+            (v.pos_enu[0] / epsilon).round() as i64,
+            (v.pos_enu[1] / epsilon).round() as i64,
+            (v.pos_enu[2] / epsilon).round() as i64,
+        ];
+        match cells.entry(key) {
+            Entry::Vacant(entry) => {
+                let slot = welded.len() as u32;
+                entry.insert(slot);
+                welded.push(v.clone());
+                counts.push(1);
+                remap.push(slot);
+            }
+            Entry::Occupied(entry) => {
+                let slot = *entry.get();
+                remap.push(slot);
+                if merge == WeldMerge::Average {
+                    let n = counts[slot as usize];
+                    let t = 1.0 / (n + 1) as f32;
+                    let existing = &mut welded[slot as usize];
+                    for i in 0..3 {
+                        existing.normal[i] += (v.normal[i] - existing.normal[i]) * t;
+                    }
+                    for i in 0..2 {
+                        existing.uv[i] += (v.uv[i] - existing.uv[i]) * t;
+                    }
+                    for i in 0..4 {
+                        existing.color[i] += (v.color[i] - existing.color[i]) * t;
+                    }
+                }
+                counts[slot as usize] += 1;
+            }
+        }
+    }
+
+    if merge == WeldMerge::Average {
+        for v in welded.iter_mut() {
+            v.normal = normalize3(v.normal);
+        }
+    }
+
+    let mut new_indices = Vec::with_capacity(indices.len());
+    for tri in indices.chunks_exact(3) {
+        let a = remap[tri[0] as usize];
+        let b = remap[tri[1] as usize];
+        let c = remap[tri[2] as usize];
+        if a == b || b == c || a == c {
+            continue;
+        }
+        if is_degenerate_triangle(&welded[a as usize], &welded[b as usize], &welded[c as usize]) {
+            continue;
+        }
+        new_indices.push(a);
+        new_indices.push(b);
+        new_indices.push(c);
+    }
+
+    (welded, new_indices)
+}
+
+/// Adapts `weld_vertices` to a `PartBuilder`'s flat non-indexed triangle
+/// soup: re-derives each vertex's ENU position via `geo.transform_local`
+/// (the same trick `add_tile_skirts` uses, since `PartBuilder` only stores
+/// local-space positions), welds, then expands the deduplicated vertices
+/// back through the rewritten index buffer so the soup stays non-indexed.
+fn weld_part(part: &mut PartBuilder, geo: &GeoContext, epsilon: f64, merge: WeldMerge) {
+    let vertex_count = part.positions.len() / 3;
+    if vertex_count == 0 {
+        return;
+    }
+    let has_normals = part.normals.len() == part.positions.len();
+    let has_uvs = part.uvs.len() * 3 == part.positions.len() * 2;
+    let has_colors = part.colors.len() * 3 == part.positions.len() * 4;
+
+    let mut verts = Vec::with_capacity(vertex_count);
+    for i in 0..vertex_count {
+        let pos_local = [
+            part.positions[i * 3] as f64,
+            part.positions[i * 3 + 1] as f64,
+            part.positions[i * 3 + 2] as f64,
+        ];
+        let pos_enu = geo.transform_local(pos_local);
+        let normal = if has_normals {
+            [
+                part.normals[i * 3],
+                part.normals[i * 3 + 1],
+                part.normals[i * 3 + 2],
+            ]
+        } else {
+            [0.0; 3]
+        };
+        let uv = if has_uvs {
+            [part.uvs[i * 2], part.uvs[i * 2 + 1]]
+        } else {
+            [0.0; 2]
+        };
+        let color = if has_colors {
+            [
+                part.colors[i * 4],
+                part.colors[i * 4 + 1],
+                part.colors[i * 4 + 2],
+                part.colors[i * 4 + 3],
+            ]
+        } else {
+            [1.0; 4]
+        };
+        verts.push(Vertex {
+            pos_local,
+            pos_enu,
+            normal,
+            uv,
+            color,
+            tangent: [0.0, 0.0, 0.0, 1.0],
+        });
+    }
+
+    let indices: Vec<u32> = (0..vertex_count as u32).collect();
+    let (welded, new_indices) = weld_vertices(&verts, &indices, epsilon, merge);
+
+    let mut positions = Vec::with_capacity(new_indices.len() * 3);
+    let mut normals = Vec::with_capacity(if has_normals { new_indices.len() * 3 } else { 0 });
+    let mut uvs = Vec::with_capacity(if has_uvs { new_indices.len() * 2 } else { 0 });
+    let mut colors = Vec::with_capacity(if has_colors { new_indices.len() * 4 } else { 0 });
+    for &i in &new_indices {
+        let v = &welded[i as usize];
+        positions.extend_from_slice(&[
+            v.pos_local[0] as f32,
+            v.pos_local[1] as f32,
+            v.pos_local[2] as f32,
+        ]);
+        if has_normals {
+            normals.extend_from_slice(&v.normal);
+        }
+        if has_uvs {
+            uvs.extend_from_slice(&v.uv);
+        }
+        if has_colors {
+            colors.extend_from_slice(&v.color);
+        }
+    }
+    part.positions = positions;
+    part.normals = normals;
+    part.uvs = uvs;
+    part.colors = colors;
+}
+
+/// Alternative to `export_tileset`: builds a part-level BVH over `scene`
+/// (see the `bvh` module) instead of binning triangles into a spatial
+/// grid, and emits each BVH node as a 3D Tiles node with a `box` bounding
+/// volume. Leaves write their parts out as a single GLB each; interior
+/// nodes are refinement-only (no `content`). Suited to scenes made of many
+/// discrete, already-reasonably-sized parts rather than one dense mesh
+/// that needs triangle-level splitting.
+pub fn export_bvh_tileset(scene: &SceneData, output_dir: &Path, options: &TilesetOptions) -> Result<()> {
+    if scene.parts.is_empty() {
+        bail!("no mesh data found in FBX");
+    }
+
+    let geo = GeoContext::new(
+        options.origin_lat,
+        options.origin_lon,
+        options.origin_height,
+        options.heading,
+        options.scale,
+        Ellipsoid::WGS84,
+    );
+
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("create output dir {}", output_dir.display()))?;
+    let tiles_dir = output_dir.join("tiles");
+    fs::create_dir_all(&tiles_dir)
+        .with_context(|| format!("create tiles dir {}", tiles_dir.display()))?;
+
+    let mut texture_cache = if options.embed_textures {
+        None
+    } else {
+        let textures_dir = output_dir.join("textures");
+        fs::create_dir_all(&textures_dir)
+            .with_context(|| format!("create textures dir {}", textures_dir.display()))?;
+        Some(TextureCache::new(textures_dir, "../textures"))
+    };
+
+    let max_parts_per_leaf = options.bvh_max_parts_per_leaf.unwrap_or(8).max(1);
+    let root = bvh::build_bvh(scene, max_parts_per_leaf);
+
+    let mut next_tile_id = 0usize;
+    let mut root_value = bvh_node_to_json(
+        &root,
+        scene,
+        &tiles_dir,
+        options,
+        &mut texture_cache,
+        &mut next_tile_id,
+    )?;
+    let root_error = root_value["geometricError"].as_f64().unwrap_or(0.0);
+    root_value["transform"] = json!(geo.transform_matrix());
+    root_value["refine"] = json!("REPLACE");
+
+    let tileset = json!({
+        "asset": {
+            "version": "1.1",
+            "generator": "ufbx_rust+bvh"
+        },
+        "geometricError": root_error,
+        "root": root_value,
+    });
+
+    let tileset_path = output_dir.join("tileset.json");
+    let tileset_bytes = serde_json::to_vec_pretty(&tileset)?;
+    write_compressed_file(
+        &tileset_path,
+        &tileset_bytes,
+        options.tile_compression,
+        options.compression_level,
+    )?;
+
+    Ok(())
+}
+
+fn bvh_node_to_json(
+    node: &bvh::BvhNode,
+    scene: &SceneData,
+    tiles_dir: &Path,
+    options: &TilesetOptions,
+    texture_cache: &mut Option<TextureCache>,
+    next_tile_id: &mut usize,
+) -> Result<serde_json::Value> {
+    let bounds = node.bounds().padded(1e-3);
+    let geometric_error = bvh::geometric_error(&bounds, options.scale);
+    let bounding_volume = json!({ "box": bvh_box(&bounds) });
+
+    match node {
+        bvh::BvhNode::Leaf { parts, .. } => {
+            let part_refs: Vec<&MeshPart> = parts.iter().map(|&i| &scene.parts[i]).collect();
+            let builder_map = parts_to_builder_map(&part_refs);
+
+            let id = *next_tile_id;
+            *next_tile_id += 1;
+            let filename = format!("bvh_{id:04}.glb");
+            let path = tiles_dir.join(&filename);
+            write_tile_glb(
+                &builder_map,
+                &scene.materials,
+                scene.right_axis,
+                scene.up_axis,
+                &path,
+                texture_cache.as_mut(),
+                &options.texture_options,
+                options.mesh_compression,
+                options.tile_compression,
+                options.compression_level,
+                options.weld_vertices,
+                options.weld_vertices_epsilon,
+            )?;
+
+            Ok(json!({
+                "boundingVolume": bounding_volume,
+                "geometricError": geometric_error,
+                "content": { "uri": format!("tiles/{filename}") },
+            }))
+        }
+        bvh::BvhNode::Interior { children, .. } => {
+            let mut child_json = Vec::with_capacity(children.len());
+            for child in children {
+                child_json.push(bvh_node_to_json(
+                    child,
+                    scene,
+                    tiles_dir,
+                    options,
+                    texture_cache,
+                    next_tile_id,
+                )?);
+            }
+            Ok(json!({
+                "boundingVolume": bounding_volume,
+                "geometricError": geometric_error,
+                "children": child_json,
+            }))
+        }
+    }
+}
+
+/// 3D Tiles `box` bounding volume: center followed by the three half-axis
+/// vectors. `Aabb` is always axis-aligned in local mesh space, so the
+/// half-axis vectors are just the half-extents along X/Y/Z; the root
+/// tile's `transform` carries the whole box into world space.
+fn bvh_box(bounds: &bvh::Aabb) -> [f32; 12] {
+    let c = bounds.centroid();
+    let e = bounds.extent();
+    [
+        c[0], c[1], c[2],
+        e[0] * 0.5, 0.0, 0.0,
+        0.0, e[1] * 0.5, 0.0,
+        0.0, 0.0, e[2] * 0.5,
+    ]
+}
+
+/// Groups `parts` by `material_index` into the same `PartBuilder` shape
+/// `write_tile_glb` expects, so a BVH leaf can reuse it unchanged instead
+/// of duplicating its material-remap/GLB-writing logic.
+fn parts_to_builder_map(parts: &[&MeshPart]) -> HashMap<usize, PartBuilder> {
+    let mut map: HashMap<usize, PartBuilder> = HashMap::new();
+    for part in parts {
+        let entry = map.entry(part.material_index).or_insert_with(|| PartBuilder {
+            name: part.name.clone(),
+            material_index: part.material_index,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            colors: Vec::new(),
+        });
+        entry.positions.extend_from_slice(&part.positions);
+        entry.normals.extend_from_slice(&part.normals);
+        entry.uvs.extend_from_slice(&part.uvs);
+        entry.colors.extend_from_slice(&part.colors);
+    }
+    map
+}
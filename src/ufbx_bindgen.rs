@@ -0,0 +1,8 @@
+//! Raw, bindgen-generated `ufbx_*` declarations straight from `vendor/ufbx/ufbx.h`.
+//! Regenerated by `build.rs` on every build, so it always matches the
+//! vendored library. `ufbx_sys` is the hand-written wrapper the loader
+//! actually calls today; this module exists so new FFI surface can be
+//! reached without hand-mirroring another struct first.
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals, dead_code)]
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
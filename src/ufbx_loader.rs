@@ -3,6 +3,7 @@ use crate::ufbx_sys::{
     UfbxMaterialInfo, UfbxMeshPartInfo, UfbxTextureRef,
 };
 use anyhow::{bail, Result};
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 use std::path::{Path, PathBuf};
@@ -14,6 +15,45 @@ pub enum TextureSource {
     File(PathBuf),
 }
 
+/// glTF `alphaMode`. Defaults to `Opaque`, matching the glTF spec default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    Mask,
+    Blend,
+}
+
+impl AlphaMode {
+    pub fn from_ufbx(value: i32) -> Self {
+        match value {
+            1 => AlphaMode::Mask,
+            2 => AlphaMode::Blend,
+            _ => AlphaMode::Opaque,
+        }
+    }
+}
+
+/// `KHR_materials_clearcoat` parameters. Only present when the source
+/// material actually has a clearcoat coat (`clearcoat_factor > 0`).
+#[derive(Clone, Debug)]
+pub struct ClearcoatParams {
+    pub factor: f32,
+    pub roughness: f32,
+    pub texture: Option<TextureSource>,
+    pub roughness_texture: Option<TextureSource>,
+}
+
+/// `KHR_materials_sheen` parameters. Only present when the source material
+/// actually specifies a sheen lobe (non-black color or non-zero roughness).
+#[derive(Clone, Debug)]
+pub struct SheenParams {
+    pub color: [f32; 3],
+    pub roughness: f32,
+    pub color_texture: Option<TextureSource>,
+    pub roughness_texture: Option<TextureSource>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Material {
     pub name: Option<String>,
@@ -25,6 +65,19 @@ pub struct Material {
     pub base_color_texture: Option<TextureSource>,
     pub normal_texture: Option<TextureSource>,
     pub emissive_texture: Option<TextureSource>,
+    /// glTF convention: roughness in G, metalness in B; occlusion may be
+    /// packed into R by tools that merge all three into one texture.
+    pub metallic_roughness_texture: Option<TextureSource>,
+    pub occlusion_texture: Option<TextureSource>,
+    pub occlusion_strength: f32,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub clearcoat: Option<ClearcoatParams>,
+    pub transmission_factor: f32,
+    pub transmission_texture: Option<TextureSource>,
+    pub sheen: Option<SheenParams>,
+    /// Index of refraction. glTF's `KHR_materials_ior` default is 1.5.
+    pub ior: f32,
 }
 
 #[derive(Clone, Debug)]
@@ -35,6 +88,11 @@ pub struct MeshPart {
     pub normals: Vec<f32>,
     pub uvs: Vec<f32>,
     pub colors: Vec<f32>,
+    /// Triangle index buffer into the (deduplicated) attribute arrays above,
+    /// populated by `weld_scene`. `None` means every attribute array is a
+    /// flat, non-indexed triangle soup (three entries per triangle) — the
+    /// default shape every loader produces.
+    pub indices: Option<Vec<u32>>,
 }
 
 #[derive(Clone, Debug)]
@@ -81,6 +139,197 @@ pub fn flip_v(scene: &mut SceneData) {
     }
 }
 
+/// Basis-change matrix (as rows) mapping a source coordinate system onto
+/// glTF's Y-up, right-handed convention, plus whether that change flips
+/// handedness and so needs triangle winding reversed to match.
+struct AxisBasis {
+    matrix: [[f32; 3]; 3],
+    flip_winding: bool,
+}
+
+fn axis_vector(axis: AxisDir) -> Option<[f32; 3]> {
+    match axis {
+        AxisDir::PosX => Some([1.0, 0.0, 0.0]),
+        AxisDir::NegX => Some([-1.0, 0.0, 0.0]),
+        AxisDir::PosY => Some([0.0, 1.0, 0.0]),
+        AxisDir::NegY => Some([0.0, -1.0, 0.0]),
+        AxisDir::PosZ => Some([0.0, 0.0, 1.0]),
+        AxisDir::NegZ => Some([0.0, 0.0, -1.0]),
+        AxisDir::Unknown => None,
+    }
+}
+
+fn cross3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot3(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Builds the basis matrix that sends `right`/`up` (and their implied
+/// forward axis, `right × up`) onto glTF's X/Y/Z. Returns `None` for
+/// `Unknown` or a degenerate (non-orthogonal) pair, in which case the
+/// caller should leave geometry untouched rather than apply garbage.
+fn axis_basis_matrix(right: AxisDir, up: AxisDir) -> Option<AxisBasis> {
+    let r = axis_vector(right)?;
+    let u = axis_vector(up)?;
+    if dot3(r, u).abs() > 0.5 {
+        return None;
+    }
+    let f = cross3(r, u);
+    let matrix = [r, u, f];
+    // Rows are an orthonormal right-handed triple by construction (the
+    // third row is literally `r × u`), so this is always a proper rotation
+    // in practice; computed explicitly rather than assumed so a future
+    // change to how `f` is derived can't silently produce a mirrored scene.
+    let det = r[0] * (u[1] * f[2] - u[2] * f[1]) - r[1] * (u[0] * f[2] - u[2] * f[0])
+        + r[2] * (u[0] * f[1] - u[1] * f[0]);
+    Some(AxisBasis {
+        matrix,
+        flip_winding: det < 0.0,
+    })
+}
+
+fn apply_basis_to_vec3_slice(values: &mut [f32], matrix: &[[f32; 3]; 3]) {
+    for v in values.chunks_exact_mut(3) {
+        let src = [v[0], v[1], v[2]];
+        v[0] = dot3(matrix[0], src);
+        v[1] = dot3(matrix[1], src);
+        v[2] = dot3(matrix[2], src);
+    }
+}
+
+/// Swaps the second and third vertex of every triangle in `values` (stored
+/// as `stride`-wide attributes, 3 vertices per triangle), reversing winding
+/// while leaving the first vertex in place.
+fn swap_triangle_components(values: &mut [f32], stride: usize) {
+    for tri in values.chunks_exact_mut(stride * 3) {
+        let (first_two, third) = tri.split_at_mut(stride * 2);
+        first_two[stride..].swap_with_slice(third);
+    }
+}
+
+fn reverse_triangle_winding(part: &mut MeshPart) {
+    swap_triangle_components(&mut part.positions, 3);
+    swap_triangle_components(&mut part.normals, 3);
+    swap_triangle_components(&mut part.uvs, 2);
+    swap_triangle_components(&mut part.colors, 4);
+}
+
+/// Bakes `scene.right_axis`/`scene.up_axis` into every part's `positions`
+/// and `normals` in place, instead of leaving the source axis convention as
+/// metadata glTF viewers never consult. A no-op if either axis is `Unknown`
+/// or they don't form a valid orthogonal pair. Opt-in, like `flip_v`: most
+/// ufbx-loaded scenes are already Y-up and need no correction.
+pub fn bake_axis_convention(scene: &mut SceneData) {
+    let Some(basis) = axis_basis_matrix(scene.right_axis, scene.up_axis) else {
+        return;
+    };
+
+    for part in &mut scene.parts {
+        apply_basis_to_vec3_slice(&mut part.positions, &basis.matrix);
+        apply_basis_to_vec3_slice(&mut part.normals, &basis.matrix);
+        if basis.flip_winding {
+            reverse_triangle_winding(part);
+        }
+    }
+}
+
+/// Welds coincident vertices in every part of `scene` and populates each
+/// part's `indices`, shrinking the attribute arrays so shared vertices are
+/// no longer duplicated. Opt-in, like `flip_v` and `bake_axis_convention` —
+/// the unindexed, non-welded shape every loader produces stays the default.
+pub fn weld_scene(scene: &mut SceneData, epsilon: f32) {
+    for part in &mut scene.parts {
+        weld_mesh_part(part, epsilon);
+    }
+}
+
+/// Welds `part` in place: hashes each vertex as the tuple of its position
+/// (quantized to `epsilon`) plus normal/uv/color components when those
+/// attributes are present (each also quantized to `epsilon`), so vertices
+/// merge only when every one of those components rounds to the same cell. Rewrites `positions`/`normals`/`uvs`/`colors` down to the
+/// deduplicated set and fills `part.indices` with the triangle list against
+/// it, dropping any triangle that degenerated (two or three corners welding
+/// together). A non-positive `epsilon` is a no-op, matching
+/// `tiles::weld_vertices`.
+fn weld_mesh_part(part: &mut MeshPart, epsilon: f32) {
+    let vertex_count = part.positions.len() / 3;
+    if vertex_count == 0 || epsilon <= 0.0 {
+        return;
+    }
+
+    let has_normals = part.normals.len() == part.positions.len();
+    let has_uvs = part.uvs.len() * 3 == part.positions.len() * 2;
+    let has_colors = part.colors.len() * 3 == part.positions.len() * 4;
+    let quantize = |value: f32| -> i64 { (value / epsilon).round() as i64 };
+
+    let mut cells: HashMap<Vec<i64>, u32> = HashMap::new();
+    let mut welded_positions = Vec::new();
+    let mut welded_normals = Vec::new();
+    let mut welded_uvs = Vec::new();
+    let mut welded_colors = Vec::new();
+    let mut indices = Vec::with_capacity(vertex_count);
+
+    for i in 0..vertex_count {
+        let mut key = Vec::with_capacity(12);
+        key.push(quantize(part.positions[i * 3]));
+        key.push(quantize(part.positions[i * 3 + 1]));
+        key.push(quantize(part.positions[i * 3 + 2]));
+        if has_normals {
+            key.push(quantize(part.normals[i * 3]));
+            key.push(quantize(part.normals[i * 3 + 1]));
+            key.push(quantize(part.normals[i * 3 + 2]));
+        }
+        if has_uvs {
+            key.push(quantize(part.uvs[i * 2]));
+            key.push(quantize(part.uvs[i * 2 + 1]));
+        }
+        if has_colors {
+            key.push(quantize(part.colors[i * 4]));
+            key.push(quantize(part.colors[i * 4 + 1]));
+            key.push(quantize(part.colors[i * 4 + 2]));
+            key.push(quantize(part.colors[i * 4 + 3]));
+        }
+
+        let slot = *cells.entry(key).or_insert_with(|| {
+            let slot = (welded_positions.len() / 3) as u32;
+            welded_positions.extend_from_slice(&part.positions[i * 3..i * 3 + 3]);
+            if has_normals {
+                welded_normals.extend_from_slice(&part.normals[i * 3..i * 3 + 3]);
+            }
+            if has_uvs {
+                welded_uvs.extend_from_slice(&part.uvs[i * 2..i * 2 + 2]);
+            }
+            if has_colors {
+                welded_colors.extend_from_slice(&part.colors[i * 4..i * 4 + 4]);
+            }
+            slot
+        });
+        indices.push(slot);
+    }
+
+    let mut kept_indices = Vec::with_capacity(indices.len());
+    for tri in indices.chunks_exact(3) {
+        let (a, b, c) = (tri[0], tri[1], tri[2]);
+        if a == b || b == c || a == c {
+            continue;
+        }
+        kept_indices.extend_from_slice(&[a, b, c]);
+    }
+
+    part.positions = welded_positions;
+    part.normals = welded_normals;
+    part.uvs = welded_uvs;
+    part.colors = welded_colors;
+    part.indices = Some(kept_indices);
+}
+
 fn read_optional_c_string(ptr: *const c_char) -> Option<String> {
     if ptr.is_null() {
         return None;
@@ -113,6 +362,28 @@ fn texture_from_ref(tex: &UfbxTextureRef, base_dir: &Path) -> Option<TextureSour
 }
 
 fn material_from_raw(raw: &UfbxMaterialInfo, base_dir: &Path) -> Material {
+    let clearcoat = if raw.clearcoat_factor > 0.0 {
+        Some(ClearcoatParams {
+            factor: raw.clearcoat_factor,
+            roughness: raw.clearcoat_roughness,
+            texture: texture_from_ref(&raw.clearcoat_texture, base_dir),
+            roughness_texture: texture_from_ref(&raw.clearcoat_roughness_texture, base_dir),
+        })
+    } else {
+        None
+    };
+
+    let sheen = if raw.sheen_color != [0.0, 0.0, 0.0] || raw.sheen_roughness > 0.0 {
+        Some(SheenParams {
+            color: raw.sheen_color,
+            roughness: raw.sheen_roughness,
+            color_texture: texture_from_ref(&raw.sheen_color_texture, base_dir),
+            roughness_texture: texture_from_ref(&raw.sheen_roughness_texture, base_dir),
+        })
+    } else {
+        None
+    };
+
     Material {
         name: read_optional_c_string(raw.name),
         base_color: raw.base_color,
@@ -123,6 +394,20 @@ fn material_from_raw(raw: &UfbxMaterialInfo, base_dir: &Path) -> Material {
         base_color_texture: texture_from_ref(&raw.base_color_texture, base_dir),
         normal_texture: texture_from_ref(&raw.normal_texture, base_dir),
         emissive_texture: texture_from_ref(&raw.emissive_texture, base_dir),
+        metallic_roughness_texture: texture_from_ref(&raw.metallic_roughness_texture, base_dir),
+        occlusion_texture: texture_from_ref(&raw.occlusion_texture, base_dir),
+        occlusion_strength: if raw.occlusion_texture.path.is_null() && raw.occlusion_texture.content.is_null() {
+            1.0
+        } else {
+            raw.occlusion_strength
+        },
+        alpha_mode: AlphaMode::from_ufbx(raw.alpha_mode),
+        alpha_cutoff: if raw.alpha_cutoff > 0.0 { raw.alpha_cutoff } else { 0.5 },
+        clearcoat,
+        transmission_factor: raw.transmission_factor,
+        transmission_texture: texture_from_ref(&raw.transmission_texture, base_dir),
+        sheen,
+        ior: if raw.ior > 0.0 { raw.ior } else { 1.5 },
     }
 }
 
@@ -164,6 +449,7 @@ fn mesh_part_from_raw(raw: &UfbxMeshPartInfo) -> MeshPart {
         normals,
         uvs,
         colors,
+        indices: None,
     }
 }
 
@@ -218,3 +504,103 @@ pub fn load_scene(path: &Path) -> Result<SceneData> {
 
 #[allow(dead_code)]
 fn _ensure_linked(_scene: &UfbxExportScene) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_CONCRETE_AXES: [AxisDir; 6] = [
+        AxisDir::PosX,
+        AxisDir::NegX,
+        AxisDir::PosY,
+        AxisDir::NegY,
+        AxisDir::PosZ,
+        AxisDir::NegZ,
+    ];
+
+    fn sample_scene(right: AxisDir, up: AxisDir) -> SceneData {
+        SceneData {
+            materials: Vec::new(),
+            right_axis: right,
+            up_axis: up,
+            parts: vec![MeshPart {
+                name: None,
+                material_index: 0,
+                positions: vec![1.0, 2.0, 3.0],
+                normals: vec![0.0, 0.0, 1.0],
+                uvs: vec![],
+                colors: vec![],
+                indices: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn axis_basis_matrix_rows_match_right_up_forward_for_every_concrete_pair() {
+        for &right in &ALL_CONCRETE_AXES {
+            for &up in &ALL_CONCRETE_AXES {
+                let r = axis_vector(right).unwrap();
+                let u = axis_vector(up).unwrap();
+                if dot3(r, u).abs() > 0.5 {
+                    continue;
+                }
+                let basis = axis_basis_matrix(right, up)
+                    .expect("orthogonal right/up pair should produce a basis");
+                assert_eq!(basis.matrix[0], r);
+                assert_eq!(basis.matrix[1], u);
+                assert_eq!(basis.matrix[2], cross3(r, u));
+            }
+        }
+    }
+
+    #[test]
+    fn axis_basis_matrix_is_none_when_right_axis_is_unknown() {
+        for &up in &ALL_CONCRETE_AXES {
+            assert!(axis_basis_matrix(AxisDir::Unknown, up).is_none());
+        }
+    }
+
+    #[test]
+    fn axis_basis_matrix_is_none_when_up_axis_is_unknown() {
+        for &right in &ALL_CONCRETE_AXES {
+            assert!(axis_basis_matrix(right, AxisDir::Unknown).is_none());
+        }
+    }
+
+    #[test]
+    fn bake_axis_convention_is_identity_for_native_gltf_axes() {
+        let mut scene = sample_scene(AxisDir::PosX, AxisDir::PosY);
+        let positions_before = scene.parts[0].positions.clone();
+        let normals_before = scene.parts[0].normals.clone();
+        bake_axis_convention(&mut scene);
+        assert_eq!(scene.parts[0].positions, positions_before);
+        assert_eq!(scene.parts[0].normals, normals_before);
+    }
+
+    #[test]
+    fn bake_axis_convention_is_a_no_op_when_right_axis_is_unknown() {
+        let mut scene = sample_scene(AxisDir::Unknown, AxisDir::PosY);
+        let positions_before = scene.parts[0].positions.clone();
+        bake_axis_convention(&mut scene);
+        assert_eq!(scene.parts[0].positions, positions_before);
+    }
+
+    #[test]
+    fn bake_axis_convention_is_a_no_op_when_up_axis_is_unknown() {
+        let mut scene = sample_scene(AxisDir::PosX, AxisDir::Unknown);
+        let positions_before = scene.parts[0].positions.clone();
+        bake_axis_convention(&mut scene);
+        assert_eq!(scene.parts[0].positions, positions_before);
+    }
+
+    #[test]
+    fn bake_axis_convention_remaps_a_z_up_scene_onto_y_up() {
+        let mut scene = sample_scene(AxisDir::PosX, AxisDir::PosZ);
+        scene.parts[0].positions = vec![1.0, 2.0, 3.0];
+        scene.parts[0].normals = vec![0.0, 0.0, 1.0];
+        bake_axis_convention(&mut scene);
+        // right stays X, up (source Z) becomes glTF Y, forward (source -Y) becomes glTF Z.
+        assert_eq!(scene.parts[0].positions, vec![1.0, 3.0, -2.0]);
+        assert_eq!(scene.parts[0].normals, vec![0.0, 1.0, 0.0]);
+    }
+}
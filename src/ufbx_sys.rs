@@ -18,6 +18,23 @@ pub struct UfbxMaterialInfo {
     pub base_color_texture: UfbxTextureRef,
     pub normal_texture: UfbxTextureRef,
     pub emissive_texture: UfbxTextureRef,
+    pub metallic_roughness_texture: UfbxTextureRef,
+    pub occlusion_texture: UfbxTextureRef,
+    pub occlusion_strength: f32,
+    /// glTF `alphaMode` as a ufbx-style int enum: 0 = Opaque, 1 = Mask, 2 = Blend.
+    pub alpha_mode: i32,
+    pub alpha_cutoff: f32,
+    pub clearcoat_factor: f32,
+    pub clearcoat_roughness: f32,
+    pub clearcoat_texture: UfbxTextureRef,
+    pub clearcoat_roughness_texture: UfbxTextureRef,
+    pub transmission_factor: f32,
+    pub transmission_texture: UfbxTextureRef,
+    pub sheen_color: [f32; 3],
+    pub sheen_roughness: f32,
+    pub sheen_color_texture: UfbxTextureRef,
+    pub sheen_roughness_texture: UfbxTextureRef,
+    pub ior: f32,
 }
 
 #[repr(C)]